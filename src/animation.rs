@@ -0,0 +1,461 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Transform and opacity animations that don't rebuild the display list.
+//!
+//! An `Animator` hands out layers. Each layer is pushed as a stacking context
+//! whose transform and opacity are WebRender property bindings, and every
+//! frame the current values are sent along with `generate_frame` as
+//! `DynamicProperties`. See the `spin` example.
+
+use std::f32::consts::PI;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+use webrender::api::*;
+
+/// How progress through a keyframe segment maps to progress between its
+/// values. The named curves are the CSS ones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    Ease,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// The control points `(x1, y1, x2, y2)` of a cubic bézier from (0, 0)
+    /// to (1, 1), like CSS's `cubic-bezier()`.
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    /// Maps `t` in `[0, 1]` to eased progress.
+    pub fn apply(&self, t: f32) -> f32 {
+        match *self {
+            Easing::Linear => t,
+            Easing::Ease => cubic_bezier(0.25, 0.1, 0.25, 1.0, t),
+            Easing::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, t),
+            Easing::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, t),
+            Easing::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, t),
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    fn sample(a1: f32, a2: f32, t: f32) -> f32 {
+        // The curve's start and end are fixed at 0 and 1.
+        let u = 1.0 - t;
+        3.0 * u * u * t * a1 + 3.0 * u * t * t * a2 + t * t * t
+    }
+
+    if x <= 0.0 || x >= 1.0 {
+        return x.max(0.0).min(1.0);
+    }
+
+    // x(t) is monotonic for control points in [0, 1], so bisect for the t
+    // that gives x and evaluate y there. Plenty precise for animation.
+    let (mut low, mut high) = (0.0, 1.0);
+    let mut t = x;
+    for _ in 0..20 {
+        if sample(x1, x2, t) < x {
+            low = t;
+        } else {
+            high = t;
+        }
+        t = (low + high) / 2.0;
+    }
+    sample(y1, y2, t)
+}
+
+/// Values that keyframes can be interpolated between.
+pub trait Interpolate: Copy {
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+/// A 2D transform kept in decomposed form, so interpolating between two of
+/// them rotates rather than squashing through a matrix lerp.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2D {
+    pub translation: LayoutVector2D,
+    /// Clockwise, in degrees.
+    pub rotation: f32,
+    pub scale: f32,
+}
+
+impl Transform2D {
+    pub fn identity() -> Transform2D {
+        Transform2D {
+            translation: LayoutVector2D::zero(),
+            rotation: 0.0,
+            scale: 1.0,
+        }
+    }
+
+    pub fn translate(x: f32, y: f32) -> Transform2D {
+        Transform2D { translation: LayoutVector2D::new(x, y), .. Transform2D::identity() }
+    }
+
+    pub fn rotate(degrees: f32) -> Transform2D {
+        Transform2D { rotation: degrees, .. Transform2D::identity() }
+    }
+
+    pub fn scale(scale: f32) -> Transform2D {
+        Transform2D { scale, .. Transform2D::identity() }
+    }
+
+    /// Scales and rotates around `origin`, then translates.
+    pub fn to_layout_transform(&self, origin: LayoutPoint) -> LayoutTransform {
+        let (sin, cos) = (self.rotation * PI / 180.0).sin_cos();
+        let (m11, m12) = (self.scale * cos, self.scale * sin);
+        let (m21, m22) = (-self.scale * sin, self.scale * cos);
+        let m41 = origin.x + self.translation.x - (m11 * origin.x + m21 * origin.y);
+        let m42 = origin.y + self.translation.y - (m12 * origin.x + m22 * origin.y);
+        LayoutTransform::row_major(m11, m12, 0.0, 0.0,
+                                   m21, m22, 0.0, 0.0,
+                                   0.0, 0.0, 1.0, 0.0,
+                                   m41, m42, 0.0, 1.0)
+    }
+}
+
+impl Interpolate for Transform2D {
+    fn interpolate(&self, other: &Transform2D, t: f32) -> Transform2D {
+        Transform2D {
+            translation: self.translation + (other.translation - self.translation) * t,
+            rotation: self.rotation.interpolate(&other.rotation, t),
+            scale: self.scale.interpolate(&other.scale, t),
+        }
+    }
+}
+
+/// What happens once an animation reaches its end.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Repeat {
+    /// Hold the last value.
+    Once,
+    /// Jump back to the start.
+    Loop,
+    /// Play backwards, then forwards again.
+    Alternate,
+}
+
+#[derive(Clone, Debug)]
+struct Keyframe<T> {
+    offset: f32,
+    value: T,
+    easing: Easing,
+}
+
+/// Values at offsets in `[0, 1]` of a duration, like a CSS `@keyframes` rule.
+/// The easing of a keyframe applies to the segment that starts at it.
+#[derive(Clone, Debug)]
+pub struct Animation<T> {
+    keyframes: Vec<Keyframe<T>>,
+    /// In seconds.
+    duration: f32,
+    easing: Easing,
+    repeat: Repeat,
+}
+
+impl<T: Interpolate> Animation<T> {
+    /// An animation from `from` to `to` over `duration` seconds, played once
+    /// with `Easing::Linear` until configured otherwise.
+    pub fn new(duration: f32, from: T, to: T) -> Animation<T> {
+        Animation {
+            keyframes: vec![
+                Keyframe { offset: 0.0, value: from, easing: Easing::Linear },
+                Keyframe { offset: 1.0, value: to, easing: Easing::Linear },
+            ],
+            duration,
+            easing: Easing::Linear,
+            repeat: Repeat::Once,
+        }
+    }
+
+    /// Adds a keyframe at `offset`, replacing any already there.
+    pub fn keyframe(mut self, offset: f32, value: T) -> Animation<T> {
+        let offset = offset.max(0.0).min(1.0);
+        let easing = self.easing;
+        self.keyframes.retain(|keyframe| keyframe.offset != offset);
+        let index = self.keyframes.iter().position(|keyframe| keyframe.offset > offset)
+            .unwrap_or(self.keyframes.len());
+        self.keyframes.insert(index, Keyframe { offset, value, easing });
+        self
+    }
+
+    /// Sets the easing of every keyframe added so far, and of those added
+    /// later unless this is called again.
+    pub fn easing(mut self, easing: Easing) -> Animation<T> {
+        self.easing = easing;
+        for keyframe in &mut self.keyframes {
+            keyframe.easing = easing;
+        }
+        self
+    }
+
+    pub fn repeat(mut self, repeat: Repeat) -> Animation<T> {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn is_finished(&self, time: f32) -> bool {
+        self.repeat == Repeat::Once && time >= self.duration
+    }
+
+    /// The value `time` seconds after the animation started.
+    pub fn sample(&self, time: f32) -> T {
+        let progress = if self.duration <= 0.0 {
+            1.0
+        } else {
+            let cycles = time.max(0.0) / self.duration;
+            match self.repeat {
+                Repeat::Once => cycles.min(1.0),
+                Repeat::Loop => cycles.fract(),
+                Repeat::Alternate => {
+                    let fract = cycles.fract();
+                    if cycles as u32 % 2 == 0 { fract } else { 1.0 - fract }
+                }
+            }
+        };
+
+        let next = self.keyframes.iter().position(|keyframe| keyframe.offset > progress);
+        match next {
+            Some(0) => self.keyframes[0].value,
+            Some(index) => {
+                let (from, to) = (&self.keyframes[index - 1], &self.keyframes[index]);
+                let t = (progress - from.offset) / (to.offset - from.offset);
+                from.value.interpolate(&to.value, from.easing.apply(t))
+            }
+            None => self.keyframes[self.keyframes.len() - 1].value,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayerId(usize);
+
+struct Layer {
+    transform_key: PropertyBindingKey<LayoutTransform>,
+    opacity_key: PropertyBindingKey<f32>,
+    transform: Option<Animation<Transform2D>>,
+    opacity: Option<Animation<f32>>,
+    /// Where the layer was last pushed, in its own coordinates. Rotation and
+    /// scaling happen around its center.
+    origin: LayoutPoint,
+}
+
+// Every example in a document sends its properties with the same
+// `generate_frame`, so keys have to be unique across all animators.
+static NEXT_BINDING_KEY: AtomicUsize = ATOMIC_USIZE_INIT;
+
+fn next_binding_key<T>() -> PropertyBindingKey<T> {
+    PropertyBindingKey::new(NEXT_BINDING_KEY.fetch_add(1, Ordering::Relaxed) as u64 + 1)
+}
+
+/// Owns the animations of a document's layers and turns them into the
+/// `DynamicProperties` for each frame.
+pub struct Animator {
+    layers: Vec<Layer>,
+    /// Seconds since the animations started.
+    time: f32,
+    paused: bool,
+}
+
+impl Animator {
+    pub fn new() -> Animator {
+        Animator {
+            layers: Vec::new(),
+            time: 0.0,
+            paused: false,
+        }
+    }
+
+    pub fn add_layer(&mut self) -> LayerId {
+        let transform_key = next_binding_key();
+        let opacity_key = next_binding_key();
+        self.layers.push(Layer {
+            transform_key,
+            opacity_key,
+            transform: None,
+            opacity: None,
+            origin: LayoutPoint::zero(),
+        });
+        LayerId(self.layers.len() - 1)
+    }
+
+    pub fn animate_transform(&mut self, layer: LayerId, animation: Animation<Transform2D>) {
+        self.layers[layer.0].transform = Some(animation);
+    }
+
+    pub fn animate_opacity(&mut self, layer: LayerId, animation: Animation<f32>) {
+        self.layers[layer.0].opacity = Some(animation);
+    }
+
+    /// Pushes a stacking context for `layer` whose animated properties are
+    /// bound to the animator. Pop it with `builder.pop_stacking_context()`.
+    pub fn push_stacking_context(&mut self,
+                                 builder: &mut DisplayListBuilder,
+                                 layer: LayerId,
+                                 bounds: LayoutRect) {
+        let layer = &mut self.layers[layer.0];
+        layer.origin = LayoutPoint::new(bounds.size.width / 2.0, bounds.size.height / 2.0);
+
+        let transform = layer.transform.as_ref()
+            .map(|_| PropertyBinding::Binding(layer.transform_key));
+        let filters = match layer.opacity {
+            Some(_) => vec![FilterOp::Opacity(PropertyBinding::Binding(layer.opacity_key))],
+            None => Vec::new(),
+        };
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      bounds,
+                                      transform,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      filters);
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        if !self.paused {
+            self.time += dt;
+        }
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Starts every animation over.
+    pub fn restart(&mut self) {
+        self.time = 0.0;
+    }
+
+    /// Whether any animation still changes with time.
+    pub fn is_animating(&self) -> bool {
+        !self.paused && self.layers.iter().any(|layer| {
+            layer.transform.as_ref().map_or(false, |a| !a.is_finished(self.time)) ||
+            layer.opacity.as_ref().map_or(false, |a| !a.is_finished(self.time))
+        })
+    }
+
+    /// The current value of every bound property, for `generate_frame`.
+    pub fn dynamic_properties(&self) -> DynamicProperties {
+        let mut properties = DynamicProperties {
+            transforms: Vec::new(),
+            floats: Vec::new(),
+        };
+        for layer in &self.layers {
+            if let Some(ref animation) = layer.transform {
+                properties.transforms.push(PropertyValue {
+                    key: layer.transform_key,
+                    value: animation.sample(self.time).to_layout_transform(layer.origin),
+                });
+            }
+            if let Some(ref animation) = layer.opacity {
+                properties.floats.push(PropertyValue {
+                    key: layer.opacity_key,
+                    value: animation.sample(self.time),
+                });
+            }
+        }
+        properties
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-3;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < EPSILON, "{} is not close to {}", actual, expected);
+    }
+
+    #[test]
+    fn easings_start_at_0_and_end_at_1() {
+        let easings = [
+            Easing::Linear,
+            Easing::Ease,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+            Easing::CubicBezier(0.1, 0.7, 1.0, 0.1),
+        ];
+        for easing in &easings {
+            assert_close(easing.apply(0.0), 0.0);
+            assert_close(easing.apply(1.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn bezier_easings_match_css() {
+        // Symmetric control points pass through the middle.
+        assert_close(Easing::EaseInOut.apply(0.5), 0.5);
+        assert_close(Easing::CubicBezier(0.25, 0.25, 0.75, 0.75).apply(0.3), 0.3);
+        // From the CSS `ease` curve.
+        assert_close(Easing::Ease.apply(0.5), 0.8024);
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+    }
+
+    #[test]
+    fn interpolates_between_keyframes() {
+        let animation = Animation::new(2.0, 0.0, 10.0).keyframe(0.5, 100.0);
+        assert_close(animation.sample(0.0), 0.0);
+        assert_close(animation.sample(0.5), 50.0);
+        assert_close(animation.sample(1.0), 100.0);
+        assert_close(animation.sample(1.5), 55.0);
+    }
+
+    #[test]
+    fn keyframe_easing_applies_to_its_segment() {
+        let animation = Animation::new(1.0, 0.0, 1.0).easing(Easing::EaseInOut);
+        assert_close(animation.sample(0.5), 0.5);
+        assert!(animation.sample(0.25) < 0.25);
+    }
+
+    #[test]
+    fn holds_the_last_value_at_the_end() {
+        let animation = Animation::new(1.0, 0.0, 10.0);
+        assert_close(animation.sample(1.0), 10.0);
+        assert_close(animation.sample(5.0), 10.0);
+        assert!(animation.is_finished(1.0));
+        assert!(!animation.is_finished(0.5));
+    }
+
+    #[test]
+    fn loops_back_to_the_start() {
+        let animation = Animation::new(1.0, 0.0, 10.0).repeat(Repeat::Loop);
+        assert_close(animation.sample(1.0), 0.0);
+        assert_close(animation.sample(1.25), 2.5);
+        assert!(!animation.is_finished(10.0));
+    }
+
+    #[test]
+    fn alternate_reverses_on_odd_cycles() {
+        let animation = Animation::new(1.0, 0.0, 10.0).repeat(Repeat::Alternate);
+        assert_close(animation.sample(0.25), 2.5);
+        // The end of the first cycle is the start of the reversed one.
+        assert_close(animation.sample(1.0), 10.0);
+        assert_close(animation.sample(1.25), 7.5);
+        assert_close(animation.sample(2.0), 0.0);
+        assert_close(animation.sample(2.25), 2.5);
+        assert_close(animation.sample(3.75), 2.5);
+    }
+
+    #[test]
+    fn zero_duration_jumps_to_the_end() {
+        let animation = Animation::new(0.0, 0.0, 10.0);
+        assert_close(animation.sample(0.0), 10.0);
+    }
+}
@@ -0,0 +1,482 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Benchmarks: synthetic scenes of a given size rendered headlessly for a
+//! fixed number of frames, with their frame times and display list sizes
+//! compared against a saved baseline.
+
+use boilerplate::Example;
+use cli;
+use glutin;
+use headless::HeadlessRenderer;
+use profiling::{self, FrameTimings};
+use serde_json;
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use text::{Font, FontError};
+use webrender::api::*;
+
+/// A scene generator and how big a scene it makes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StressScene {
+    /// `columns` by `rows` rects filling the viewport.
+    RectGrid { columns: usize, rows: usize },
+    /// Stacking contexts nested `depth` deep, each drawing a rect.
+    StackingContexts { depth: usize },
+    /// Rects each in their own rounded clip.
+    Clips { count: usize },
+    /// Scroll frames each with a rect twice their size inside.
+    ScrollFrames { count: usize },
+    /// A single glyph run of `chars` characters, wrapped to the viewport.
+    Text { chars: usize },
+}
+
+/// A few sizes of each scene.
+pub const DEFAULT_SCENES: &'static str = "grid:10x10,grid:100x100,stacking:100,stacking:1000,\
+                                          clips:1000,scroll:1000,text:10000";
+
+/// Parses the `--bench-scenes` syntax, which is also what `Display` writes:
+/// `grid:COLUMNSxROWS`, `stacking:DEPTH`, `clips:N`, `scroll:N` or `text:CHARS`.
+pub fn parse_scene(spec: &str) -> Result<StressScene, String> {
+    let error = || format!("invalid scene {:?}, expected grid:COLUMNSxROWS, stacking:DEPTH, \
+                            clips:N, scroll:N or text:CHARS", spec);
+    let mut parts = spec.splitn(2, ':');
+    let (kind, param) = match (parts.next(), parts.next()) {
+        (Some(kind), Some(param)) => (kind, param),
+        _ => return Err(error()),
+    };
+    let count = || param.parse::<usize>().map_err(|_| error());
+    match kind {
+        "grid" => {
+            let mut dims = param.split('x').map(|dim| dim.parse::<usize>());
+            match (dims.next(), dims.next(), dims.next()) {
+                (Some(Ok(columns)), Some(Ok(rows)), None) => Ok(StressScene::RectGrid { columns, rows }),
+                _ => Err(error()),
+            }
+        }
+        "stacking" => Ok(StressScene::StackingContexts { depth: count()? }),
+        "clips" => Ok(StressScene::Clips { count: count()? }),
+        "scroll" => Ok(StressScene::ScrollFrames { count: count()? }),
+        "text" => Ok(StressScene::Text { chars: count()? }),
+        _ => Err(error()),
+    }
+}
+
+impl fmt::Display for StressScene {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StressScene::RectGrid { columns, rows } => write!(f, "grid:{}x{}", columns, rows),
+            StressScene::StackingContexts { depth } => write!(f, "stacking:{}", depth),
+            StressScene::Clips { count } => write!(f, "clips:{}", count),
+            StressScene::ScrollFrames { count } => write!(f, "scroll:{}", count),
+            StressScene::Text { chars } => write!(f, "text:{}", chars),
+        }
+    }
+}
+
+impl StressScene {
+    /// Fails only for text, when there's no font to draw it with.
+    pub fn create(&self) -> Result<StressExample, FontError> {
+        let font = match *self {
+            StressScene::Text { .. } => Some(Font::system_default()?),
+            _ => None,
+        };
+        Ok(StressExample {
+            scene: *self,
+            font,
+        })
+    }
+}
+
+/// `count` cells laid out in `columns`, spread over `layout_size` with a
+/// pixel between them while there's room for it.
+fn cells(count: usize, columns: usize, layout_size: LayoutSize) -> Vec<LayoutRect> {
+    let columns = columns.max(1);
+    let rows = ((count + columns - 1) / columns).max(1);
+    let size = LayoutSize::new(layout_size.width / columns as f32, layout_size.height / rows as f32);
+    let gap = if size.width > 2.0 && size.height > 2.0 { 1.0 } else { 0.0 };
+    (0..count).map(|index| {
+        let origin = LayoutPoint::new((index % columns) as f32 * size.width,
+                                      (index / columns) as f32 * size.height);
+        LayoutRect::new(origin, LayoutSize::new(size.width - gap, size.height - gap))
+    }).collect()
+}
+
+/// Roughly square cells for `count` items in `layout_size`.
+fn square_cells(count: usize, layout_size: LayoutSize) -> Vec<LayoutRect> {
+    let aspect = layout_size.width / layout_size.height.max(1.0);
+    let columns = (count as f32 * aspect).sqrt().ceil() as usize;
+    cells(count, columns, layout_size)
+}
+
+fn cell_color(index: usize, count: usize) -> ColorF {
+    let t = index as f32 / count.max(1) as f32;
+    ColorF::new(t, 1.0 - t, 0.5, 1.0)
+}
+
+const LOREM: &'static str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
+                             eiusmod tempor incididunt ut labore et dolore magna aliqua. ";
+
+/// Draws a `StressScene`. The display list is the same every frame.
+pub struct StressExample {
+    scene: StressScene,
+    font: Option<Font>,
+}
+
+impl Example for StressExample {
+    fn render(&mut self,
+              api: &RenderApi,
+              builder: &mut DisplayListBuilder,
+              resources: &mut ResourceUpdates,
+              layout_size: LayoutSize,
+              _pipeline_id: PipelineId,
+              _document_id: DocumentId) {
+        let bounds = LayoutRect::new(LayoutPoint::zero(), layout_size);
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      bounds,
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+
+        match self.scene {
+            StressScene::RectGrid { columns, rows } => {
+                let count = columns * rows;
+                for (index, cell) in cells(count, columns, layout_size).into_iter().enumerate() {
+                    builder.push_rect(cell, None, cell_color(index, count));
+                }
+            }
+            StressScene::StackingContexts { depth } => {
+                // Each one is offset from its parent, so they drift towards
+                // the bottom right and wrap around every 256.
+                let step = (layout_size.width.min(layout_size.height) / 2.0) / 256.0;
+                for level in 0..depth {
+                    let offset = if level > 0 && level % 256 == 0 { -255.0 * step } else { step };
+                    builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                                  LayoutRect::new(LayoutPoint::new(offset, offset),
+                                                                  LayoutSize::zero()),
+                                                  None,
+                                                  TransformStyle::Flat,
+                                                  None,
+                                                  MixBlendMode::Normal,
+                                                  Vec::new());
+                    let mut color = cell_color(level, depth);
+                    color.a = 0.25;
+                    builder.push_rect(LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(100.0, 100.0)),
+                                      None, color);
+                }
+                for _ in 0..depth {
+                    builder.pop_stacking_context();
+                }
+            }
+            StressScene::Clips { count } => {
+                for (index, cell) in square_cells(count, layout_size).into_iter().enumerate() {
+                    let radius = cell.size.width.min(cell.size.height) / 4.0;
+                    let complex = vec![ComplexClipRegion::new(cell, BorderRadius::uniform(radius))];
+                    let clip_id = builder.define_clip(None, cell, complex, None);
+                    builder.push_clip_id(clip_id);
+                    builder.push_rect(cell, None, cell_color(index, count));
+                    builder.pop_clip_id();
+                }
+            }
+            StressScene::ScrollFrames { count } => {
+                for (index, cell) in square_cells(count, layout_size).into_iter().enumerate() {
+                    let content = LayoutRect::new(cell.origin, cell.size * 2.0);
+                    let clip_id = builder.define_scroll_frame(None,
+                                                              content,
+                                                              cell,
+                                                              vec![],
+                                                              None,
+                                                              ScrollSensitivity::ScriptAndInputEvents);
+                    builder.push_clip_id(clip_id);
+                    builder.push_rect(content, None, cell_color(index, count));
+                    builder.pop_clip_id();
+                }
+            }
+            StressScene::Text { chars } => {
+                if let Some(ref mut font) = self.font {
+                    let text: String = LOREM.chars().cycle().take(chars).collect();
+                    let font = font.instance(api, resources, 12.0);
+                    let run = font.layout(&text, LayoutPoint::zero(), Some(layout_size.width));
+                    builder.push_glyph_run(&font, &run, ColorF::new(1.0, 1.0, 1.0, 1.0));
+                }
+            }
+        }
+
+        builder.pop_stacking_context();
+    }
+
+    fn on_event(&mut self, _event: glutin::WindowEvent, _api: &RenderApi, _document_id: DocumentId) -> bool {
+        false
+    }
+}
+
+pub struct BenchOptions {
+    pub scenes: Vec<StressScene>,
+    /// Frames measured per scene.
+    pub frames: usize,
+    /// Frames rendered before measuring, so shader compilation and glyph
+    /// rasterization aren't counted.
+    pub warmup: usize,
+    /// Results to compare against.
+    pub baseline: Option<PathBuf>,
+    /// Where to save the results, to use as a baseline later.
+    pub save: Option<PathBuf>,
+    /// How many percent slower than the baseline counts as a regression.
+    pub threshold: f64,
+}
+
+impl BenchOptions {
+    pub fn from_cli(cli: &cli::Options) -> BenchOptions {
+        BenchOptions {
+            scenes: cli.bench_scenes.clone(),
+            frames: cli.bench_frames,
+            warmup: cli.bench_warmup,
+            baseline: cli.bench_baseline.clone(),
+            save: cli.bench_save.clone(),
+            threshold: cli.bench_threshold,
+        }
+    }
+}
+
+/// Percentiles are in milliseconds.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BenchResult {
+    pub scene: String,
+    pub frames: usize,
+    /// From building the display list to the frame being finished.
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    pub build_p50_ms: f64,
+    pub render_p50_ms: f64,
+    /// `None` without GPU timer queries.
+    pub gpu_p50_ms: Option<f64>,
+    pub display_list_bytes: usize,
+}
+
+/// What `--bench-save` writes.
+#[derive(Deserialize, Serialize)]
+pub struct Baseline {
+    /// In device pixels.
+    pub size: (u32, u32),
+    pub results: Vec<BenchResult>,
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> io::Result<Baseline> {
+        serde_json::from_reader(BufReader::new(File::open(path)?))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), self)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+}
+
+// Nearest rank, `values` must be sorted.
+fn percentile(values: &[f64], percent: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let rank = (percent / 100.0 * values.len() as f64).ceil() as usize;
+    values[rank.max(1).min(values.len()) - 1]
+}
+
+fn sorted_ms<F: Fn(&FrameTimings) -> Duration>(frames: &[FrameTimings], duration: F) -> Vec<f64> {
+    let mut values: Vec<_> = frames.iter().map(|frame| profiling::as_ms(duration(frame))).collect();
+    // Converted durations are never NaN.
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values
+}
+
+fn summarize(scene: &StressScene, frames: &[FrameTimings]) -> BenchResult {
+    let total = sorted_ms(frames, FrameTimings::total);
+    let build = sorted_ms(frames, FrameTimings::build);
+    let render = sorted_ms(frames, |frame| frame.render);
+    let mut gpu: Vec<_> = frames.iter()
+        .filter_map(|frame| frame.gpu_ns)
+        .map(|ns| ns as f64 * 1e-6)
+        .collect();
+    gpu.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    BenchResult {
+        scene: scene.to_string(),
+        frames: frames.len(),
+        p50_ms: percentile(&total, 50.0),
+        p90_ms: percentile(&total, 90.0),
+        p99_ms: percentile(&total, 99.0),
+        max_ms: total.last().cloned().unwrap_or(0.0),
+        build_p50_ms: percentile(&build, 50.0),
+        render_p50_ms: percentile(&render, 50.0),
+        gpu_p50_ms: if gpu.is_empty() { None } else { Some(percentile(&gpu, 50.0)) },
+        display_list_bytes: frames.iter().map(FrameTimings::display_list_bytes).max().unwrap_or(0),
+    }
+}
+
+// Fails when there's no font for text or no GL context, for example.
+fn run_benchmark(scene: &StressScene,
+                 options: &BenchOptions,
+                 cli: &cli::Options) -> Result<BenchResult, Box<error::Error>> {
+    let mut example = scene.create()?;
+    let device_pixel_ratio = cli.device_pixel_ratio.unwrap_or(1.0);
+    let mut renderer = HeadlessRenderer::new(cli.headless_size(), device_pixel_ratio, cli, None)?;
+    for _ in 0..options.warmup + options.frames {
+        renderer.render_frame(&mut example)?;
+    }
+    let result = summarize(scene, &renderer.profiler().frames()[options.warmup..]);
+    renderer.deinit();
+    Ok(result)
+}
+
+fn percent_change(current: f64, baseline: f64) -> f64 {
+    if baseline > 0.0 { (current / baseline - 1.0) * 100.0 } else { 0.0 }
+}
+
+/// Runs every scene and prints its results, along with how they compare to
+/// the baseline if there is one. Returns false if a scene got slower than
+/// the baseline by more than the threshold, or couldn't be run.
+pub fn run_benchmarks(options: &BenchOptions, cli: &cli::Options) -> bool {
+    let size = cli.headless_size();
+    let baseline = match options.baseline {
+        Some(ref path) => match Baseline::load(path) {
+            Ok(baseline) => {
+                if baseline.size != (size.width, size.height) {
+                    warn!("The baseline was rendered at {}x{}, not {}x{}",
+                          baseline.size.0, baseline.size.1, size.width, size.height);
+                }
+                Some(baseline)
+            }
+            Err(e) => {
+                error!("Couldn't load the baseline {}: {}", path.display(), e);
+                return false;
+            }
+        },
+        None => None,
+    };
+
+    let mut passed = true;
+    let mut results = Vec::new();
+    println!("{} frames per scene at {}x{}, times in ms", options.frames, size.width, size.height);
+    println!("{:<16} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>10}",
+             "scene", "p50", "p90", "p99", "max", "build", "render", "gpu", "dl bytes");
+
+    for scene in &options.scenes {
+        let result = match run_benchmark(scene, options, cli) {
+            Ok(result) => result,
+            Err(e) => {
+                passed = false;
+                println!("{:<16} couldn't run: {}", scene, e);
+                continue;
+            }
+        };
+        let gpu = result.gpu_p50_ms.map_or("-".to_string(), |ms| format!("{:.2}", ms));
+        println!("{:<16} {:>8.2} {:>8.2} {:>8.2} {:>8.2} {:>8.2} {:>8.2} {:>8} {:>10}",
+                 result.scene, result.p50_ms, result.p90_ms, result.p99_ms, result.max_ms,
+                 result.build_p50_ms, result.render_p50_ms, gpu, result.display_list_bytes);
+
+        if let Some(ref baseline) = baseline {
+            match baseline.results.iter().find(|old| old.scene == result.scene) {
+                Some(old) => {
+                    let p50 = percent_change(result.p50_ms, old.p50_ms);
+                    let p90 = percent_change(result.p90_ms, old.p90_ms);
+                    let regressed = p50 > options.threshold || p90 > options.threshold;
+                    println!("{:<16} p50 {:+.1}%, p90 {:+.1}%, display list {:+} bytes{}",
+                             "  vs baseline", p50, p90,
+                             result.display_list_bytes as i64 - old.display_list_bytes as i64,
+                             if regressed { ", REGRESSED" } else { "" });
+                    if regressed {
+                        passed = false;
+                    }
+                }
+                None => println!("{:<16} not in the baseline", "  vs baseline"),
+            }
+        }
+        results.push(result);
+    }
+
+    if let Some(ref path) = options.save {
+        let baseline = Baseline {
+            size: (size.width, size.height),
+            results,
+        };
+        match baseline.save(path) {
+            Ok(()) => info!("Saved the results to {}", path.display()),
+            Err(e) => {
+                error!("Couldn't save the results to {}: {}", path.display(), e);
+                passed = false;
+            }
+        }
+    }
+    passed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_of_no_samples_is_zero() {
+        assert_eq!(percentile(&[], 50.0), 0.0);
+        assert_eq!(percentile(&[], 100.0), 0.0);
+    }
+
+    #[test]
+    fn percentile_of_one_sample_is_that_sample() {
+        for &percent in &[0.0, 1.0, 50.0, 99.0, 100.0] {
+            assert_eq!(percentile(&[4.5], percent), 4.5);
+        }
+    }
+
+    #[test]
+    fn percentile_uses_the_nearest_rank() {
+        let values: Vec<f64> = (1..11).map(|value| value as f64).collect();
+        assert_eq!(percentile(&values, 0.0), 1.0);
+        assert_eq!(percentile(&values, 10.0), 1.0);
+        assert_eq!(percentile(&values, 11.0), 2.0);
+        assert_eq!(percentile(&values, 50.0), 5.0);
+        assert_eq!(percentile(&values, 90.0), 9.0);
+        assert_eq!(percentile(&values, 99.0), 10.0);
+        assert_eq!(percentile(&values, 100.0), 10.0);
+    }
+
+    #[test]
+    fn percentile_of_two_samples() {
+        assert_eq!(percentile(&[1.0, 2.0], 50.0), 1.0);
+        assert_eq!(percentile(&[1.0, 2.0], 51.0), 2.0);
+    }
+
+    #[test]
+    fn parses_every_scene_kind() {
+        assert_eq!(parse_scene("grid:20x30"), Ok(StressScene::RectGrid { columns: 20, rows: 30 }));
+        assert_eq!(parse_scene("stacking:5"), Ok(StressScene::StackingContexts { depth: 5 }));
+        assert_eq!(parse_scene("clips:6"), Ok(StressScene::Clips { count: 6 }));
+        assert_eq!(parse_scene("scroll:7"), Ok(StressScene::ScrollFrames { count: 7 }));
+        assert_eq!(parse_scene("text:8"), Ok(StressScene::Text { chars: 8 }));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for spec in DEFAULT_SCENES.split(',') {
+            let scene = parse_scene(spec).unwrap();
+            assert_eq!(scene.to_string(), spec);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_scenes() {
+        let invalid = ["", "grid", "grid:", "grid:10", "grid:1x2x3", "grid:ax2", "stacking:",
+                       "stacking:-1", "clips:many", "circles:10", ":10"];
+        for spec in &invalid {
+            assert!(parse_scene(spec).is_err(), "{:?} parsed", spec);
+        }
+    }
+}
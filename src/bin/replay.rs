@@ -0,0 +1,152 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Plays back a recording made with `--record=PATH`.
+//!
+//! Usage: `replay [--play] PATH`
+//!
+//! Each press of the right arrow or space sends the next recorded frame to
+//! WebRender. With `--play` (or after pressing P) frames are sent as fast as
+//! the renderer produces them.
+
+extern crate clap;
+extern crate glutin;
+#[macro_use]
+extern crate log;
+extern crate webrender;
+extern crate webrender_playground;
+
+use clap::{App, Arg};
+use glutin::GlContext;
+use std::path::PathBuf;
+use std::process;
+use webrender::api::*;
+use webrender_playground::boilerplate::{self, Notifier};
+use webrender_playground::capture::Replay;
+use webrender_playground::error::PlaygroundError;
+use webrender_playground::{cli, logging};
+
+fn main() {
+    let matches = App::new("replay")
+        .about("Plays back a recording made with the playground's --record option")
+        .arg(Arg::with_name("recording")
+             .value_name("PATH")
+             .required(true)
+             .help("The recording to play"))
+        .arg(Arg::with_name("play")
+             .long("play")
+             .help("Play frames continuously instead of stepping with the arrow keys"))
+        .get_matches();
+
+    if let Err(e) = logging::init(None, None) {
+        eprintln!("{}", e);
+    }
+
+    // clap has already exited with a usage message if it's missing.
+    let path = PathBuf::from(matches.value_of("recording").expect("the recording is a required argument"));
+    let mut replay = Replay::open(&path).unwrap_or_else(|e| {
+        error!("Couldn't open {}: {}", path.display(), e);
+        process::exit(1);
+    });
+    let mut playing = matches.is_present("play");
+
+    let mut events_loop = glutin::EventsLoop::new();
+    let title = format!("WebRender Replay - {}", path.display());
+    let (window, gl) = boilerplate::create_window(&events_loop, &title, &cli::Options::default())
+        .unwrap_or_else(|e| {
+            error!("{}", e);
+            process::exit(1);
+        });
+    let (mut width, mut height) = window.get_inner_size_pixels().unwrap_or_else(|| {
+        error!("{}", PlaygroundError::WindowGone);
+        process::exit(1);
+    });
+
+    let opts = webrender::RendererOptions {
+        debug: true,
+        device_pixel_ratio: window.hidpi_factor(),
+        .. webrender::RendererOptions::default()
+    };
+    let (mut renderer, sender) = webrender::renderer::Renderer::new(gl, opts).unwrap_or_else(|e| {
+        error!("{}", PlaygroundError::from(e));
+        process::exit(1);
+    });
+    let api = sender.create_api();
+    let document_id = api.add_document(DeviceUintSize::new(width, height));
+    renderer.set_render_notifier(Box::new(Notifier::new(events_loop.create_proxy())));
+
+    let mut frames_played = 0;
+    let mut finished = false;
+    let mut failed = false;
+    let mut advance = true;
+    window.show();
+
+    events_loop.run_forever(|event| {
+        match event {
+            glutin::Event::Awakened => advance = playing,
+            glutin::Event::WindowEvent { event, .. } => match event {
+                glutin::WindowEvent::Resized(w, h) => {
+                    window.resize(w, h);
+                    width = w;
+                    height = h;
+                    let size = DeviceUintSize::new(width, height);
+                    api.set_window_parameters(document_id, size, DeviceUintRect::new(DeviceUintPoint::zero(), size));
+                    api.generate_frame(document_id, None);
+                }
+                glutin::WindowEvent::Closed |
+                glutin::WindowEvent::KeyboardInput {
+                    input: glutin::KeyboardInput { virtual_keycode: Some(glutin::VirtualKeyCode::Escape), .. }, ..
+                } => return glutin::ControlFlow::Break,
+                glutin::WindowEvent::KeyboardInput {
+                    input: glutin::KeyboardInput {
+                        state: glutin::ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                    ..
+                } => match key {
+                    glutin::VirtualKeyCode::Right |
+                    glutin::VirtualKeyCode::Space => advance = true,
+                    glutin::VirtualKeyCode::P => {
+                        playing = !playing;
+                        advance = playing;
+                    }
+                    _ => (),
+                },
+                _ => (),
+            },
+            _ => (),
+        }
+
+        if advance && !finished {
+            match replay.play_frame(&api, document_id) {
+                Ok(true) => frames_played += 1,
+                Ok(false) => {
+                    finished = true;
+                    info!("Reached the end of the recording after {} frames", frames_played);
+                }
+                Err(e) => {
+                    finished = true;
+                    failed = true;
+                    error!("Stopped after {} frames: {}", frames_played, e);
+                }
+            }
+        }
+        advance = false;
+
+        renderer.update();
+        renderer.render(DeviceUintSize::new(width, height));
+        if let Err(e) = window.swap_buffers() {
+            error!("{}", PlaygroundError::from(e));
+            failed = true;
+            return glutin::ControlFlow::Break;
+        }
+        glutin::ControlFlow::Continue
+    });
+
+    renderer.deinit();
+    if failed {
+        process::exit(1);
+    }
+}
@@ -2,39 +2,72 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use capture::Recorder;
+use cli;
+use context::{self, Capabilities};
+use error::PlaygroundError;
 use gleam::gl;
 use glutin;
-use std::env;
-use std::path::PathBuf;
+use hit_test::{HitTag, HitTester};
+use images::LoadedImage;
+use profiling::{DisplayListTiming, FrameProfiler};
+use screenshot;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use text::{Font, FontInstance, GlyphRun};
+use watcher::FileWatcher;
 use webrender;
 use webrender::api::*;
 use webrender::renderer::{PROFILER_DBG, RENDER_TARGET_DBG, TEXTURE_CACHE_DBG};
-use webrender::renderer::ExternalImageHandler;
-// use support;
+use webrender::renderer::{DebugFlags, ExternalImageHandler, InitError, Renderer};
 
 use glutin::GlContext;
 
-struct Notifier {
+pub struct Notifier {
     loop_proxy: glutin::EventsLoopProxy,
+    // Set before waking the loop up, since `Awakened` doesn't say which
+    // window's renderer has a frame.
+    frame_ready: Option<Arc<AtomicBool>>,
 }
 
 impl Notifier {
-    fn new(loop_proxy: glutin::EventsLoopProxy)-> Notifier {
+    pub fn new(loop_proxy: glutin::EventsLoopProxy)-> Notifier {
         Notifier {
             loop_proxy,
+            frame_ready: None,
         }
     }
+
+    pub fn with_frame_flag(loop_proxy: glutin::EventsLoopProxy, frame_ready: Arc<AtomicBool>) -> Notifier {
+        Notifier {
+            loop_proxy,
+            frame_ready: Some(frame_ready),
+        }
+    }
+
+    fn notify(&self) {
+        if let Some(ref frame_ready) = self.frame_ready {
+            frame_ready.store(true, Ordering::SeqCst);
+        }
+        // This fails once the loop has exited, when nobody is waiting for
+        // frames anymore.
+        #[cfg(not(target_os = "android"))]
+        self.loop_proxy.wakeup().ok();
+    }
 }
 
 impl RenderNotifier for Notifier {
     fn new_frame_ready(&mut self) {
-        #[cfg(not(target_os = "android"))]
-        self.loop_proxy.wakeup().unwrap(); // TODO maybe don't unwrap
+        self.notify();
     }
 
     fn new_scroll_frame_ready(&mut self, _composite_needed: bool) {
-        #[cfg(not(target_os = "android"))]
-        self.loop_proxy.wakeup().unwrap(); // TODO maybe don't unwrap
+        self.notify();
     }
 }
 
@@ -56,6 +89,155 @@ impl HandyDandyRectBuilder for (i32, i32) {
     }
 }
 
+pub trait TextBuilder {
+    fn push_string(&mut self, font: &FontInstance, origin: LayoutPoint, text: &str, color: ColorF) -> LayoutRect;
+    fn push_glyph_run(&mut self, font: &FontInstance, run: &GlyphRun, color: ColorF);
+}
+// Allows doing `builder.push_string(&font, origin, "text", color)` with the
+// top left corner of the text at `origin`. Returns the bounds of the text.
+impl TextBuilder for DisplayListBuilder {
+    fn push_string(&mut self, font: &FontInstance, origin: LayoutPoint, text: &str, color: ColorF) -> LayoutRect {
+        let run = font.layout(text, origin, None);
+        self.push_glyph_run(font, &run, color);
+        run.bounds
+    }
+
+    fn push_glyph_run(&mut self, font: &FontInstance, run: &GlyphRun, color: ColorF) {
+        self.push_text(run.bounds, None, &run.glyphs, font.key, color, None);
+    }
+}
+
+pub trait ImageBuilder {
+    fn push_image_at(&mut self, image: &LoadedImage, origin: LayoutPoint) -> LayoutRect;
+    fn push_image_stretched(&mut self, image: &LoadedImage, bounds: LayoutRect);
+    fn push_image_tiled(&mut self, image: &LoadedImage, bounds: LayoutRect, tile_size: LayoutSize, spacing: LayoutSize);
+}
+// Allows pushing an image at its natural size, stretched to fill `bounds`, or
+// repeated over `bounds` with each copy `tile_size` big and `spacing` apart.
+// Scaling is filtered according to the image's `rendering`.
+impl ImageBuilder for DisplayListBuilder {
+    fn push_image_at(&mut self, image: &LoadedImage, origin: LayoutPoint) -> LayoutRect {
+        let bounds = LayoutRect::new(origin, image.size);
+        self.push_image_stretched(image, bounds);
+        bounds
+    }
+
+    fn push_image_stretched(&mut self, image: &LoadedImage, bounds: LayoutRect) {
+        self.push_image(bounds, None, bounds.size, LayoutSize::zero(), image.rendering, image.key);
+    }
+
+    fn push_image_tiled(&mut self, image: &LoadedImage, bounds: LayoutRect, tile_size: LayoutSize, spacing: LayoutSize) {
+        self.push_image(bounds, None, tile_size, spacing, image.rendering, image.key);
+    }
+}
+
+/// Keys handled by the playground rather than the example.
+pub const KEY_BINDINGS: &'static [(&'static str, &'static str)] = &[
+    ("Esc", "quit"),
+    ("Tab", "switch to the next example"),
+    ("H", "show or hide the key bindings"),
+    ("P", "toggle the profiler"),
+    ("O", "toggle render target debugging"),
+    ("I", "toggle texture cache debugging"),
+    ("M", "notify memory pressure"),
+    ("S", "save a screenshot"),
+];
+
+pub fn print_help() {
+    println!("Key bindings:");
+    for &(key, action) in KEY_BINDINGS {
+        println!("  {:<4} {}", key, action);
+    }
+}
+
+const HELP_FONT_SIZE: f32 = 16.0;
+
+/// The key bindings, drawn over the example. Without a font they're printed
+/// to the console instead.
+struct HelpOverlay {
+    font: Option<Font>,
+    visible: bool,
+}
+
+impl HelpOverlay {
+    fn new() -> HelpOverlay {
+        let font = Font::system_default()
+            .map_err(|e| warn!("Key bindings will be printed to the console: {}", e))
+            .ok();
+        HelpOverlay {
+            font,
+            visible: false,
+        }
+    }
+
+    /// Returns whether the display list needs to be rebuilt.
+    fn toggle(&mut self) -> bool {
+        if self.font.is_none() {
+            print_help();
+            return false;
+        }
+        self.visible = !self.visible;
+        true
+    }
+
+    fn build(&mut self,
+             api: &RenderApi,
+             builder: &mut DisplayListBuilder,
+             resources: &mut ResourceUpdates,
+             layout_size: LayoutSize) {
+        if !self.visible {
+            return;
+        }
+        let font = match self.font {
+            Some(ref mut font) => font.instance(api, resources, HELP_FONT_SIZE),
+            None => return,
+        };
+
+        let text = KEY_BINDINGS.iter()
+            .map(|&(key, action)| format!("{}  {}", key, action))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let padding = 12.0;
+        let run = font.layout(&text, LayoutPoint::new(2.0 * padding, 2.0 * padding), None);
+        let panel = run.bounds.inflate(padding, padding);
+
+        builder.push_stacking_context(ScrollPolicy::Fixed,
+                                      LayoutRect::new(LayoutPoint::zero(), layout_size),
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+        builder.push_rect(panel, None, ColorF::new(0.0, 0.0, 0.0, 0.75));
+        builder.push_glyph_run(&font, &run, ColorF::new(1.0, 1.0, 1.0, 1.0));
+        builder.pop_stacking_context();
+    }
+}
+
+/// Parses a comma separated list of debug flag names, as passed to
+/// `--debug-flags`.
+pub fn parse_debug_flags(names: &str) -> Result<DebugFlags, String> {
+    let mut flags = DebugFlags::empty();
+    for name in names.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()) {
+        flags |= match name {
+            "profiler" => PROFILER_DBG,
+            "render-targets" => RENDER_TARGET_DBG,
+            "texture-cache" => TEXTURE_CACHE_DBG,
+            _ => return Err(format!("unknown debug flag {:?}, expected profiler, \
+                                     render-targets or texture-cache", name)),
+        };
+    }
+    Ok(flags)
+}
+
+fn toggle_debug_flag(renderer: &mut Renderer, flag: DebugFlags) {
+    let mut flags = renderer.get_debug_flags();
+    flags.toggle(flag);
+    renderer.set_debug_flags(flags);
+}
+
+pub const ROOT_BACKGROUND_COLOR: ColorF = ColorF { r: 0.3, g: 0.0, b: 0.0, a: 1.0 };
+
 pub trait Example {
     fn render(&mut self,
               api: &RenderApi,
@@ -68,200 +250,1036 @@ pub trait Example {
                 event: glutin::WindowEvent,
                 api: &RenderApi,
                 document_id: DocumentId) -> bool;
-    fn get_external_image_handler(&self) -> Option<Box<ExternalImageHandler>> {
+    /// Called whenever a renderer is created, which happens again when the
+    /// shaders are reloaded. `gl` is current while the handler is used.
+    fn get_external_image_handler(&self, _gl: &Rc<gl::Gl>) -> Option<Box<ExternalImageHandler>> {
         None
     }
-}
-
-pub fn main_wrapper(example: &mut Example,
-                    options: Option<webrender::RendererOptions>)
-{
-    let args: Vec<String> = env::args().collect();
-    let res_path = if args.len() > 1 {
-        Some(PathBuf::from(&args[1]))
-    } else {
+    /// Files to watch while the window is open, see `on_file_changed`.
+    fn watched_files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+    /// Called when one of the `watched_files` changed on disk. Returns
+    /// whether the display list needs to be rebuilt.
+    fn on_file_changed(&mut self, _path: &Path) -> bool {
+        false
+    }
+    /// Whether `tick` should be called every frame. While this is true the
+    /// event loop polls and redraws continuously instead of sleeping.
+    fn wants_animation(&self) -> bool {
+        false
+    }
+    /// Advances animations by `dt` seconds. Returns whether the display list
+    /// needs to be rebuilt.
+    fn tick(&mut self, _dt: f32) -> bool {
+        false
+    }
+    /// Values for the property bindings in the display list, sent with every
+    /// frame. See `animation::Animator`.
+    fn dynamic_properties(&self) -> Option<DynamicProperties> {
         None
-    };
-
-    let mut events_loop = glutin::EventsLoop::new();
-    let window_builder = glutin::WindowBuilder::new()
-        .with_multitouch()
-        .with_visibility(false)
-        .with_title("WebRender Playground");
-    let context = glutin::ContextBuilder::new()
-        .with_gl(glutin::GlRequest::GlThenGles {
-            opengl_version: (3, 2),
-            opengles_version: (3, 0)
-        });
-    let window = glutin::GlWindow::new(window_builder, context, &events_loop).unwrap();
-
-    unsafe { window.make_current().ok() };
-
-    println!("Pixel format of the window's GL context: {:?}", window.get_pixel_format());
-
-    let gl = match gl::GlType::default() {
-        gl::GlType::Gl => unsafe { gl::GlFns::load_with(|symbol| window.get_proc_address(symbol) as *const _) },
-        gl::GlType::Gles => unsafe { gl::GlesFns::load_with(|symbol| window.get_proc_address(symbol) as *const _) },
-    };
-    // let gl = unsafe { gl::GlesFns::load_with(|symbol| window.get_proc_address(symbol) as *const _) };
-
-    println!("OpenGL version {}", gl.get_string(gl::VERSION));
-    println!("Shader resource path: {:?}", res_path);
-    // let sgl = gl.clone();
-
-    let (mut width, mut height) = window.get_inner_size_pixels().unwrap();
-
-    let opts = webrender::RendererOptions {
-        resource_override_path: res_path,
-        debug: true,
-        precache_shaders: false,
-        device_pixel_ratio: window.hidpi_factor(),
-        .. options.unwrap_or(webrender::RendererOptions::default())
-    };
-
-    let size = DeviceUintSize::new(width, height);
-    let (mut renderer, sender) = webrender::renderer::Renderer::new(gl, opts).unwrap();
-    let api = sender.create_api();
-    let document_id = api.add_document(size);
-
-    let notifier = Box::new(Notifier::new(events_loop.create_proxy()));
-    renderer.set_render_notifier(notifier);
-
-    if let Some(external_image_handler) = example.get_external_image_handler() {
-        renderer.set_external_image_handler(external_image_handler);
     }
+    /// The rects the example tagged in its last display list, used to find
+    /// the tags for `on_hover` and `on_click`.
+    fn hit_tester(&self) -> Option<&HitTester> {
+        None
+    }
+    /// Called when the cursor moves onto another tag, or off every tag.
+    /// Returns whether the display list needs to be rebuilt.
+    fn on_hover(&mut self, _tag: Option<HitTag>) -> bool {
+        false
+    }
+    /// Called when the left button is pressed over a tag. Returns whether
+    /// the display list needs to be rebuilt.
+    fn on_click(&mut self, _tag: HitTag) -> bool {
+        false
+    }
+}
 
-    let epoch = Epoch(0);
-    let root_background_color = ColorF::new(0.3, 0.0, 0.0, 1.0);
+/// Builds a fresh display list with `example` and sends it to the document,
+/// then asks for a new frame to be generated. Returns how long building and
+/// sending took.
+pub fn render_example(example: &mut Example,
+                      api: &RenderApi,
+                      document_id: DocumentId,
+                      pipeline_id: PipelineId,
+                      epoch: Epoch,
+                      layout_size: LayoutSize) -> DisplayListTiming {
+    let timing = render_and_record(example, api, document_id, pipeline_id, epoch, layout_size, &[], None, None);
+    api.generate_frame(document_id, example.dynamic_properties());
+    timing
+}
 
-    let pipeline_id = PipelineId(0, 0);
-    let layout_size = LayoutSize::new(width as f32, height as f32);
+// Builds `example`'s display list followed by its `iframes` and, when given,
+// the help overlay, and sends it to WebRender and the recorder. Generating a
+// frame is left to the caller, so that several pipelines of a document can
+// be updated for the same frame.
+fn render_and_record(example: &mut Example,
+                     api: &RenderApi,
+                     document_id: DocumentId,
+                     pipeline_id: PipelineId,
+                     epoch: Epoch,
+                     layout_size: LayoutSize,
+                     iframes: &[(LayoutRect, PipelineId)],
+                     overlay: Option<&mut HelpOverlay>,
+                     recorder: Option<&mut Recorder>) -> DisplayListTiming {
+    let start = Instant::now();
     let mut builder = DisplayListBuilder::new(pipeline_id, layout_size);
     let mut resources = ResourceUpdates::new();
 
-    example.render(&api, &mut builder, &mut resources, layout_size, pipeline_id, document_id);
+    example.render(api, &mut builder, &mut resources, layout_size, pipeline_id, document_id);
+    if !iframes.is_empty() {
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      LayoutRect::new(LayoutPoint::zero(), layout_size),
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+        for &(bounds, iframe_pipeline_id) in iframes {
+            builder.push_iframe(bounds, None, iframe_pipeline_id);
+        }
+        builder.pop_stacking_context();
+    }
+    if let Some(overlay) = overlay {
+        overlay.build(api, &mut builder, &mut resources, layout_size);
+    }
+    let display_list = builder.finalize();
+    let built = start.elapsed();
+    let bytes = display_list.2.data().len();
+    if let Some(recorder) = recorder {
+        recorder.display_list(epoch, Some(ROOT_BACKGROUND_COLOR), layout_size,
+                              &display_list, &resources);
+    }
+
+    let send_start = Instant::now();
     api.set_display_list(
         document_id,
         epoch,
-        Some(root_background_color),
-        LayoutSize::new(width as f32, height as f32),
-        builder.finalize(),
+        Some(ROOT_BACKGROUND_COLOR),
+        layout_size,
+        display_list,
         true,
         resources
     );
-    api.set_root_pipeline(document_id, pipeline_id);
-    api.generate_frame(document_id, None);
+    DisplayListTiming {
+        start,
+        build: built,
+        send: send_start.elapsed(),
+        bytes,
+    }
+}
 
-    // let gl_test = support::load(sgl);
-    window.show();
+/// `[x, y, width, height]` as fractions of the window size.
+pub type PaneRect = [f32; 4];
 
-    events_loop.run_forever(|event| {
-        println!("{:?}", event);
-        match event {
-            glutin::Event::WindowEvent { event, .. } => {
-                match event {
-                    glutin::WindowEvent::Resized(w, h) => {
-                        window.resize(w, h);
-                        width = w;
-                        height = h;
-                        let size = DeviceUintSize::new(width, height);
-                        let rect = DeviceUintRect::new(DeviceUintPoint::zero(), size);
-                        api.set_window_parameters(document_id, size, rect);
-                    },
-                    glutin::WindowEvent::Closed |
-                    glutin::WindowEvent::KeyboardInput {
-                        input: glutin::KeyboardInput {virtual_keycode: Some(glutin::VirtualKeyCode::Escape), .. }, ..
-                    } => return glutin::ControlFlow::Break,
-                    /*
-                    glutin::WindowEvent::KeyboardInput(glutin::ElementState::Pressed,
-                                                 _, Some(glutin::VirtualKeyCode::P)) => {
-                        let mut flags = renderer.get_debug_flags();
-                        flags.toggle(PROFILER_DBG);
-                        renderer.set_debug_flags(flags);
+pub const FULL_WINDOW: PaneRect = [0.0, 0.0, 1.0, 1.0];
+
+/// An example along with the examples embedded in it with `push_iframe`.
+/// Every one of them gets its own pipeline and epoch, and is only rebuilt
+/// when it asks to be.
+pub struct Nested<'a> {
+    pub example: &'a mut Example,
+    /// With bounds in the coordinates of the embedding example.
+    pub iframes: Vec<(LayoutRect, Nested<'a>)>,
+}
+
+impl<'a> Nested<'a> {
+    pub fn new(example: &'a mut Example) -> Nested<'a> {
+        Nested {
+            example,
+            iframes: Vec::new(),
+        }
+    }
+
+    pub fn with_iframe(mut self, bounds: LayoutRect, iframe: Nested<'a>) -> Nested<'a> {
+        self.iframes.push((bounds, iframe));
+        self
+    }
+}
+
+/// A document of its own, covering `rect` of the window.
+pub struct Pane<'a> {
+    pub rect: PaneRect,
+    pub content: Nested<'a>,
+}
+
+impl<'a> Pane<'a> {
+    pub fn new(rect: PaneRect, content: Nested<'a>) -> Pane<'a> {
+        Pane {
+            rect,
+            content,
+        }
+    }
+
+    pub fn full_window(example: &'a mut Example) -> Pane<'a> {
+        Pane::new(FULL_WINDOW, Nested::new(example))
+    }
+}
+
+struct PipelineState<'a> {
+    example: &'a mut Example,
+    pipeline_id: PipelineId,
+    epoch: Epoch,
+    /// Where the pipeline is in its document. The root pipeline covers the
+    /// whole document, iframes keep the size they were given.
+    bounds: LayoutRect,
+    /// In this pipeline's coordinates.
+    iframes: Vec<(LayoutRect, PipelineId)>,
+}
+
+struct DocumentState<'a> {
+    document_id: DocumentId,
+    rect: PaneRect,
+    inner_rect: DeviceUintRect,
+    /// The root pipeline, followed by the iframes depth first.
+    pipelines: Vec<PipelineState<'a>>,
+}
+
+impl<'a> DocumentState<'a> {
+    /// The innermost pipeline at `point`, in document coordinates.
+    fn pipeline_at(&self, point: LayoutPoint) -> usize {
+        // Iframes come after the pipelines they're embedded in.
+        self.pipelines.iter().rposition(|pipeline| pipeline.bounds.contains(&point)).unwrap_or(0)
+    }
+
+    /// The property values of all the pipelines, which share a frame.
+    fn dynamic_properties(&self) -> Option<DynamicProperties> {
+        let mut merged: Option<DynamicProperties> = None;
+        for pipeline in &self.pipelines {
+            if let Some(properties) = pipeline.example.dynamic_properties() {
+                let merged = merged.get_or_insert_with(|| DynamicProperties {
+                    transforms: Vec::new(),
+                    floats: Vec::new(),
+                });
+                merged.transforms.extend(properties.transforms);
+                merged.floats.extend(properties.floats);
+            }
+        }
+        merged
+    }
+}
+
+// Appends the pipelines for `nested` and everything embedded in it, with
+// `bounds` in document coordinates.
+fn flatten<'a>(nested: Nested<'a>,
+               bounds: LayoutRect,
+               next_pipeline: &mut u32,
+               pipelines: &mut Vec<PipelineState<'a>>) -> PipelineId {
+    let Nested { example, iframes } = nested;
+    let pipeline_id = PipelineId(0, *next_pipeline);
+    *next_pipeline += 1;
+
+    let index = pipelines.len();
+    pipelines.push(PipelineState {
+        example,
+        pipeline_id,
+        epoch: Epoch(0),
+        bounds,
+        iframes: Vec::new(),
+    });
+    for (iframe_bounds, iframe) in iframes {
+        let document_bounds = iframe_bounds.translate(&bounds.origin.to_vector());
+        let iframe_pipeline_id = flatten(iframe, document_bounds, next_pipeline, pipelines);
+        pipelines[index].iframes.push((iframe_bounds, iframe_pipeline_id));
+    }
+    pipeline_id
+}
+
+fn pane_to_device_rect(rect: &PaneRect, size: DeviceUintSize) -> DeviceUintRect {
+    let (width, height) = (size.width as f32, size.height as f32);
+    DeviceUintRect::new(DeviceUintPoint::new((rect[0] * width).round() as u32,
+                                             (rect[1] * height).round() as u32),
+                        DeviceUintSize::new((rect[2] * width).round() as u32,
+                                            (rect[3] * height).round() as u32))
+}
+
+/// Opens a (still hidden) window, makes its context current and loads GL.
+/// The configs from `context::context_configs` are tried in order until one
+/// works.
+pub fn create_window(events_loop: &glutin::EventsLoop,
+                     title: &str,
+                     cli: &cli::Options) -> Result<(glutin::GlWindow, Rc<gl::Gl>), PlaygroundError> {
+    let mut failures = Vec::new();
+    for config in context::context_configs(cli) {
+        let mut window_builder = glutin::WindowBuilder::new()
+            .with_multitouch()
+            .with_visibility(false)
+            .with_title(title);
+        if let Some(size) = cli.window_size {
+            window_builder = window_builder.with_dimensions(size.width, size.height);
+        }
+        let context = config.apply(glutin::ContextBuilder::new())
+            .with_vsync(cli.vsync);
+        let window = match glutin::GlWindow::new(window_builder, context, events_loop) {
+            Ok(window) => window,
+            Err(e) => {
+                warn!("Couldn't create a {} context: {}", config, e);
+                failures.push((config, e));
+                continue;
+            }
+        };
+
+        unsafe { window.make_current()? };
+
+        info!("Created a {} context", config);
+        debug!("Pixel format of the window's GL context: {:?}", window.get_pixel_format());
+
+        let gl = context::load_gl(&window);
+        Capabilities::query(&*gl, window.get_api()).report();
+
+        return Ok((window, gl));
+    }
+    Err(PlaygroundError::NoContext(failures))
+}
+
+// Sets up a renderer. This happens once at startup and again every time the
+// shaders get reloaded.
+fn create_renderer(gl: Rc<gl::Gl>,
+                   opts: webrender::RendererOptions,
+                   notifier: Notifier) -> Result<(Renderer, RenderApi), InitError> {
+    let (mut renderer, sender) = Renderer::new(gl, opts)?;
+    let api = sender.create_api();
+    renderer.set_render_notifier(Box::new(notifier));
+    Ok((renderer, api))
+}
+
+// The renderer takes a single handler, so the first example with one gets
+// to provide it.
+fn external_image_handler(documents: &[DocumentState], gl: &Rc<gl::Gl>) -> Option<Box<ExternalImageHandler>> {
+    for document in documents {
+        for pipeline in &document.pipelines {
+            if let Some(handler) = pipeline.example.get_external_image_handler(gl) {
+                return Some(handler);
+            }
+        }
+    }
+    None
+}
+
+/// Everything that goes with one of the playground's windows: the GL
+/// context, the renderer and the documents the examples draw into.
+///
+/// Only the first document is recorded with `--record`, and only it shows
+/// the help overlay.
+struct PlaygroundWindow<'a> {
+    cli: &'a cli::Options,
+    window: glutin::GlWindow,
+    gl: Rc<gl::Gl>,
+    renderer: Renderer,
+    api: RenderApi,
+    documents: Vec<DocumentState<'a>>,
+    // The document and pipeline that get keyboard input, the last one
+    // clicked.
+    focus: (usize, usize),
+    cursor: (f64, f64),
+    // The document, pipeline and tag under the cursor.
+    hovered: Option<(usize, usize, HitTag)>,
+    width: u32,
+    height: u32,
+    device_pixel_ratio: f32,
+    loop_proxy: glutin::EventsLoopProxy,
+    watcher: FileWatcher,
+    recorder: Option<Recorder>,
+    help: HelpOverlay,
+    profiler: Option<FrameProfiler>,
+    // Set by the notifier when WebRender has a new frame for this window.
+    frame_ready: Arc<AtomicBool>,
+    // The main window is the one that is recorded and screenshotted.
+    primary: bool,
+    screenshot_requested: bool,
+    screenshot_count: usize,
+    last_tick: Instant,
+}
+
+impl<'a> PlaygroundWindow<'a> {
+    fn new(events_loop: &glutin::EventsLoop,
+           title: &str,
+           panes: Vec<Pane<'a>>,
+           options: Option<webrender::RendererOptions>,
+           cli: &'a cli::Options,
+           primary: bool) -> Result<PlaygroundWindow<'a>, PlaygroundError> {
+        if panes.is_empty() {
+            return Err(PlaygroundError::NoPanes);
+        }
+        let (window, gl) = create_window(events_loop, title, cli)?;
+
+        debug!("Shader resource path: {:?}", cli.resource_path);
+
+        let (width, height) = window.get_inner_size_pixels().ok_or(PlaygroundError::WindowGone)?;
+
+        let device_pixel_ratio = cli.device_pixel_ratio.unwrap_or(window.hidpi_factor());
+        let opts = cli.renderer_options(device_pixel_ratio, options);
+
+        let size = DeviceUintSize::new(width, height);
+        let loop_proxy = events_loop.create_proxy();
+        let frame_ready = Arc::new(AtomicBool::new(false));
+        let notifier = Notifier::with_frame_flag(loop_proxy.clone(), frame_ready.clone());
+        let (mut renderer, api) = create_renderer(gl.clone(), opts, notifier)?;
+
+        renderer.set_debug_flags(cli.debug_flags);
+
+        let mut next_pipeline = 0;
+        let documents: Vec<_> = panes.into_iter().map(|pane| {
+            let mut pipelines = Vec::new();
+            flatten(pane.content, LayoutRect::zero(), &mut next_pipeline, &mut pipelines);
+            let document_id = api.add_document(size);
+            api.set_root_pipeline(document_id, pipelines[0].pipeline_id);
+            DocumentState {
+                document_id,
+                rect: pane.rect,
+                inner_rect: DeviceUintRect::zero(),
+                pipelines,
+            }
+        }).collect();
+
+        if let Some(handler) = external_image_handler(&documents, &gl) {
+            renderer.set_external_image_handler(handler);
+        }
+
+        // Scene files and the like come from the examples, shaders from the
+        // resource override path.
+        let mut watched_files: Vec<_> = documents.iter()
+            .flat_map(|document| document.pipelines.iter())
+            .flat_map(|pipeline| pipeline.example.watched_files())
+            .collect();
+        watched_files.extend(cli.resource_path.clone());
+        let watcher = FileWatcher::new(watched_files, loop_proxy.clone());
+
+        // With `--record PATH` everything sent to WebRender is written to PATH,
+        // to be played back with the `replay` binary.
+        let mut recorder = match cli.record {
+            Some(ref path) if primary => Some(Recorder::create(path).map_err(PlaygroundError::Recording)?),
+            _ => None,
+        };
+        if let Some(ref mut recorder) = recorder {
+            recorder.set_root_pipeline(documents[0].pipelines[0].pipeline_id);
+        }
+
+        // Like recording, `--profile-out` only applies to the main window.
+        let profiler = match cli.profile {
+            Some(_) if primary => Some(FrameProfiler::new()),
+            _ => None,
+        };
+
+        let mut playground = PlaygroundWindow {
+            cli,
+            window,
+            gl,
+            renderer,
+            api,
+            documents,
+            focus: (0, 0),
+            cursor: (0.0, 0.0),
+            hovered: None,
+            width,
+            height,
+            device_pixel_ratio,
+            loop_proxy,
+            watcher,
+            recorder,
+            help: HelpOverlay::new(),
+            profiler,
+            frame_ready,
+            primary,
+            screenshot_requested: false,
+            screenshot_count: 0,
+            last_tick: Instant::now(),
+        };
+        playground.layout_documents();
+        playground.rebuild_all();
+        Ok(playground)
+    }
+
+    fn size(&self) -> DeviceUintSize {
+        DeviceUintSize::new(self.width, self.height)
+    }
+
+    fn all_pipelines(&self) -> Vec<(usize, usize)> {
+        let mut all = Vec::new();
+        for (doc, document) in self.documents.iter().enumerate() {
+            all.extend((0..document.pipelines.len()).map(|index| (doc, index)));
+        }
+        all
+    }
+
+    fn wants_animation(&self) -> bool {
+        self.documents.iter()
+            .flat_map(|document| document.pipelines.iter())
+            .any(|pipeline| pipeline.example.wants_animation())
+    }
+
+    // Places the documents in the window, and sizes their root pipelines to
+    // match, in layout pixels as headless runs do.
+    fn layout_documents(&mut self) {
+        let size = self.size();
+        for document in &mut self.documents {
+            document.inner_rect = pane_to_device_rect(&document.rect, size);
+            let layout_size = LayoutSize::new(document.inner_rect.size.width as f32 / self.device_pixel_ratio,
+                                              document.inner_rect.size.height as f32 / self.device_pixel_ratio);
+            document.pipelines[0].bounds = LayoutRect::new(LayoutPoint::zero(), layout_size);
+            self.api.set_window_parameters(document.document_id, size, document.inner_rect);
+        }
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.window_parameters(size, self.documents[0].inner_rect);
+        }
+    }
+
+    // Sends a new display list for one pipeline, without generating a frame.
+    fn render_pipeline(&mut self, doc: usize, index: usize) {
+        let document = &mut self.documents[doc];
+        let pipeline = &mut document.pipelines[index];
+        pipeline.epoch.0 += 1;
+
+        let overlay = if doc == 0 && index == 0 { Some(&mut self.help) } else { None };
+        let recorder = if doc == 0 { self.recorder.as_mut() } else { None };
+        let timing = render_and_record(&mut *pipeline.example, &self.api, document.document_id,
+                                       pipeline.pipeline_id, pipeline.epoch, pipeline.bounds.size,
+                                       &pipeline.iframes, overlay, recorder);
+        if let Some(ref mut profiler) = self.profiler {
+            profiler.display_list(timing);
+        }
+    }
+
+    fn generate_frame(&mut self, doc: usize) {
+        let document = &self.documents[doc];
+        let properties = document.dynamic_properties();
+        if doc == 0 {
+            if let Some(ref mut recorder) = self.recorder {
+                recorder.generate_frame(properties.clone());
+            }
+        }
+        self.api.generate_frame(document.document_id, properties);
+    }
+
+    // Rebuilds the given pipelines and generates a frame for each document
+    // that changed.
+    fn rebuild_pipelines(&mut self, mut pipelines: Vec<(usize, usize)>) {
+        pipelines.sort();
+        pipelines.dedup();
+        for &(doc, index) in &pipelines {
+            self.render_pipeline(doc, index);
+        }
+        let mut documents: Vec<_> = pipelines.iter().map(|&(doc, _)| doc).collect();
+        documents.dedup();
+        for doc in documents {
+            self.generate_frame(doc);
+        }
+    }
+
+    fn rebuild_all(&mut self) {
+        let all = self.all_pipelines();
+        self.rebuild_pipelines(all);
+    }
+
+    // Replaces the renderer with one using the current shaders, and sets the
+    // documents up again. Returns whether that worked.
+    fn reload_shaders(&mut self) -> bool {
+        // Precache so that a broken shader is reported here and we can
+        // keep using the old renderer, rather than panicking mid-frame.
+        // Options passed in by the caller only apply to the first renderer.
+        let opts = webrender::RendererOptions {
+            precache_shaders: true,
+            .. self.cli.renderer_options(self.device_pixel_ratio, None)
+        };
+        unsafe { self.window.make_current().ok() };
+        let notifier = Notifier::with_frame_flag(self.loop_proxy.clone(), self.frame_ready.clone());
+        match create_renderer(self.gl.clone(), opts, notifier) {
+            Ok((mut renderer, api)) => {
+                info!("Reloaded shaders");
+                renderer.set_debug_flags(self.renderer.get_debug_flags());
+                if let Some(handler) = external_image_handler(&self.documents, &self.gl) {
+                    renderer.set_external_image_handler(handler);
+                }
+                mem::replace(&mut self.renderer, renderer).deinit();
+                self.api = api;
+
+                let size = self.size();
+                for document in &mut self.documents {
+                    document.document_id = self.api.add_document(size);
+                    self.api.set_root_pipeline(document.document_id, document.pipelines[0].pipeline_id);
+                }
+                self.layout_documents();
+                true
+            }
+            Err(e) => {
+                error!("Failed to reload shaders: {:?}", e);
+                false
+            }
+        }
+    }
+
+    fn process_file_changes(&mut self) {
+        let mut shaders_changed = false;
+        let mut changed = Vec::new();
+        for path in self.watcher.take_changes() {
+            info!("File changed: {}", path.display());
+            match self.cli.resource_path {
+                Some(ref res_path) if path.starts_with(res_path) => shaders_changed = true,
+                _ => for (doc, document) in self.documents.iter_mut().enumerate() {
+                    for (index, pipeline) in document.pipelines.iter_mut().enumerate() {
+                        if pipeline.example.on_file_changed(&path) {
+                            changed.push((doc, index));
+                        }
                     }
-                    glutin::WindowEvent::KeyboardInput(glutin::ElementState::Pressed,
-                                                 _, Some(glutin::VirtualKeyCode::O)) => {
-                        let mut flags = renderer.get_debug_flags();
-                        flags.toggle(RENDER_TARGET_DBG);
-                        renderer.set_debug_flags(flags);
+                },
+            }
+        }
+
+        if shaders_changed && self.reload_shaders() {
+            self.rebuild_all();
+        } else {
+            self.rebuild_pipelines(changed);
+        }
+    }
+
+    // The document and pipeline under the cursor, and the cursor position in
+    // the document's layout pixels.
+    fn pipeline_at_cursor(&self) -> Option<(usize, usize, LayoutPoint)> {
+        let (x, y) = self.cursor;
+        for (doc, document) in self.documents.iter().enumerate() {
+            let rect = document.inner_rect;
+            let (x, y) = (x as f32 - rect.origin.x as f32, y as f32 - rect.origin.y as f32);
+            if x >= 0.0 && y >= 0.0 && x < rect.size.width as f32 && y < rect.size.height as f32 {
+                let point = LayoutPoint::new(x / self.device_pixel_ratio, y / self.device_pixel_ratio);
+                return Some((doc, document.pipeline_at(point), point));
+            }
+        }
+        None
+    }
+
+    // The tag under the cursor, along with the document and pipeline it's in.
+    fn hit_test_at_cursor(&self) -> Option<(usize, usize, HitTag)> {
+        let (doc, index, point) = match self.pipeline_at_cursor() {
+            Some(target) => target,
+            None => return None,
+        };
+        let pipeline = &self.documents[doc].pipelines[index];
+        let point = point - pipeline.bounds.origin.to_vector();
+        pipeline.example.hit_tester()
+            .and_then(|hit_tester| hit_tester.hit_test(point))
+            .map(|tag| (doc, index, tag))
+    }
+
+    // Tells the examples about the cursor moving between tags, and returns
+    // the pipelines that need to be rebuilt.
+    fn update_hover(&mut self) -> Vec<(usize, usize)> {
+        let hovered = self.hit_test_at_cursor();
+        let previous = mem::replace(&mut self.hovered, hovered);
+        if hovered == previous {
+            return Vec::new();
+        }
+
+        let mut changed = Vec::new();
+        if let Some((doc, index, _)) = previous {
+            let same_pipeline = hovered.map_or(false, |(new_doc, new_index, _)| (new_doc, new_index) == (doc, index));
+            if !same_pipeline && self.documents[doc].pipelines[index].example.on_hover(None) {
+                changed.push((doc, index));
+            }
+        }
+        if let Some((doc, index, tag)) = hovered {
+            if self.documents[doc].pipelines[index].example.on_hover(Some(tag)) {
+                changed.push((doc, index));
+            }
+        }
+        changed
+    }
+
+    // Passes `event` to the examples of `pipelines`, and returns those that
+    // need to be rebuilt.
+    fn deliver(&mut self, pipelines: Vec<(usize, usize)>, event: glutin::WindowEvent) -> Vec<(usize, usize)> {
+        let mut changed = Vec::new();
+        for (doc, index) in pipelines {
+            let document = &mut self.documents[doc];
+            if document.pipelines[index].example.on_event(event.clone(), &self.api, document.document_id) {
+                changed.push((doc, index));
+            }
+        }
+        changed
+    }
+
+    fn has_new_frame(&self) -> bool {
+        self.frame_ready.load(Ordering::SeqCst)
+    }
+
+    /// Returns false when the window should close.
+    fn handle_window_event(&mut self, event: glutin::WindowEvent) -> bool {
+        let changed = match event {
+            glutin::WindowEvent::Resized(w, h) => {
+                self.window.resize(w, h);
+                self.width = w;
+                self.height = h;
+                self.layout_documents();
+                // The root pipelines changed size whatever the examples say.
+                let all = self.all_pipelines();
+                let mut changed = self.deliver(all, event);
+                changed.extend((0..self.documents.len()).map(|doc| (doc, 0)));
+                changed
+            },
+            glutin::WindowEvent::Closed |
+            glutin::WindowEvent::KeyboardInput {
+                input: glutin::KeyboardInput {virtual_keycode: Some(glutin::VirtualKeyCode::Escape), .. }, ..
+            } => return false,
+            glutin::WindowEvent::KeyboardInput { input, .. } => {
+                let mut changed = Vec::new();
+                if let (glutin::ElementState::Pressed, Some(key)) = (input.state, input.virtual_keycode) {
+                    match key {
+                        glutin::VirtualKeyCode::H => if self.help.toggle() {
+                            changed.push((0, 0));
+                        },
+                        glutin::VirtualKeyCode::P => toggle_debug_flag(&mut self.renderer, PROFILER_DBG),
+                        glutin::VirtualKeyCode::O => toggle_debug_flag(&mut self.renderer, RENDER_TARGET_DBG),
+                        glutin::VirtualKeyCode::I => toggle_debug_flag(&mut self.renderer, TEXTURE_CACHE_DBG),
+                        glutin::VirtualKeyCode::M => self.api.notify_memory_pressure(),
+                        glutin::VirtualKeyCode::S => self.screenshot_requested = true,
+                        _ => (),
                     }
-                    glutin::WindowEvent::KeyboardInput(glutin::ElementState::Pressed,
-                                                 _, Some(glutin::VirtualKeyCode::I)) => {
-                        let mut flags = renderer.get_debug_flags();
-                        flags.toggle(TEXTURE_CACHE_DBG);
-                        renderer.set_debug_flags(flags);
+                }
+                let focus = self.focus;
+                changed.extend(self.deliver(vec![focus], event));
+                changed
+            },
+            glutin::WindowEvent::ReceivedCharacter(..) => {
+                let focus = self.focus;
+                self.deliver(vec![focus], event)
+            },
+            // Mouse input goes to whatever is under the cursor, with the
+            // position in its document's layout pixels.
+            glutin::WindowEvent::CursorMoved { device_id, position } => {
+                self.cursor = position;
+                let mut changed = match self.pipeline_at_cursor() {
+                    Some((doc, index, point)) => {
+                        let position = (point.x as f64, point.y as f64);
+                        self.deliver(vec![(doc, index)], glutin::WindowEvent::CursorMoved { device_id, position })
                     }
-                    glutin::WindowEvent::KeyboardInput(glutin::ElementState::Pressed,
-                                                 _, Some(glutin::VirtualKeyCode::M)) => {
-                        api.notify_memory_pressure();
+                    None => Vec::new(),
+                };
+                changed.extend(self.update_hover());
+                changed
+            },
+            glutin::WindowEvent::MouseWheel { .. } |
+            glutin::WindowEvent::MouseInput { .. } => {
+                match self.pipeline_at_cursor() {
+                    Some((doc, index, _)) => {
+                        let mut clicked = None;
+                        if let glutin::WindowEvent::MouseInput { state: glutin::ElementState::Pressed, button, .. } = event {
+                            self.focus = (doc, index);
+                            if button == glutin::MouseButton::Left {
+                                clicked = self.hit_test_at_cursor();
+                            }
+                        }
+                        let mut changed = self.deliver(vec![(doc, index)], event);
+                        if let Some((doc, index, tag)) = clicked {
+                            if self.documents[doc].pipelines[index].example.on_click(tag) {
+                                changed.push((doc, index));
+                            }
+                        }
+                        changed
                     }
-                    */
-                    _ => (),
-                }
-                if example.on_event(event, &api, document_id) {
-                    let mut builder = DisplayListBuilder::new(pipeline_id, layout_size);
-                    let mut resources = ResourceUpdates::new();
-
-                    let layout_size = LayoutSize::new(width as f32, height as f32);
-                    example.render(&api, &mut builder, &mut resources, layout_size, pipeline_id, document_id);
-                    api.set_display_list(
-                        document_id,
-                        epoch,
-                        Some(root_background_color),
-                        layout_size,
-                        builder.finalize(),
-                        true,
-                        resources
-                    );
-                    api.generate_frame(document_id, None);
+                    None => Vec::new(),
                 }
             },
-            _ => (),
+            _ => {
+                let all = self.all_pipelines();
+                self.deliver(all, event)
+            },
+        };
+        self.rebuild_pipelines(changed);
+
+        true
+    }
+
+    /// Advances the examples' animations by the time since the last tick.
+    fn tick(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_tick);
+        self.last_tick = now;
+
+        let dt = elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 * 1e-9;
+        let mut changed = Vec::new();
+        for (doc, document) in self.documents.iter_mut().enumerate() {
+            for (index, pipeline) in document.pipelines.iter_mut().enumerate() {
+                if pipeline.example.tick(dt) {
+                    changed.push((doc, index));
+                }
+            }
         }
 
-        renderer.update();
-        renderer.render(DeviceUintSize::new(width, height));
-        // gl_test.draw_frame([0.0, 1.0, 0.0, 1.0]);
-        window.swap_buffers().ok();
-        glutin::ControlFlow::Continue
-    });
+        // Documents where only bound properties changed keep their display
+        // lists and just get a new frame.
+        for doc in 0..self.documents.len() {
+            let rebuilt = changed.iter().any(|&(changed_doc, _)| changed_doc == doc);
+            if !rebuilt && self.documents[doc].dynamic_properties().is_some() {
+                self.generate_frame(doc);
+            }
+        }
+        self.rebuild_pipelines(changed);
+    }
+
+    /// Renders and presents the latest frame. Returns false when the window
+    /// should close.
+    fn draw(&mut self) -> Result<bool, PlaygroundError> {
+        unsafe { self.window.make_current()? };
+
+        let frame_arrived = self.frame_ready.swap(false, Ordering::SeqCst);
+        if frame_arrived {
+            if let Some(ref mut recorder) = self.recorder {
+                recorder.scroll_state(&self.api, self.documents[0].document_id);
+            }
+        }
+
+        // Redraws without a new frame from WebRender, e.g. on expose, would
+        // only add noise to the profile.
+        let timings = match self.profiler {
+            Some(ref mut profiler) if frame_arrived => Some(profiler.begin_frame()),
+            _ => None,
+        };
+        let update_start = Instant::now();
+        self.renderer.update();
+        let render_start = Instant::now();
+        self.renderer.render(self.size());
+        let render_end = Instant::now();
+
+        // Read back before swapping, the back buffer is undefined afterwards.
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            let path = self.cli.output_dir.join(format!("screenshot-{}.png", self.screenshot_count));
+            self.screenshot_count += 1;
+            screenshot::save_screenshot(&*self.gl, self.size(), &path);
+        }
+        // With `--screenshot PATH` the first frame is written out and we exit.
+        if frame_arrived && self.primary {
+            if let Some(ref path) = self.cli.screenshot {
+                screenshot::save_screenshot(&*self.gl, self.size(), path);
+                return Ok(false);
+            }
+        }
+
+        let swap_start = Instant::now();
+        self.window.swap_buffers()?;
+        if let (Some(profiler), Some(mut timings)) = (self.profiler.as_mut(), timings) {
+            timings.update = render_start - update_start;
+            timings.render = render_end - render_start;
+            timings.swap = swap_start.elapsed();
+            profiler.end_frame(timings, &mut self.renderer);
+        }
+        Ok(true)
+    }
 
-    renderer.deinit();
+    fn deinit(self) {
+        if let (Some(profiler), Some(path)) = (self.profiler.as_ref(), self.cli.profile.as_ref()) {
+            match profiler.write(path) {
+                Ok(()) => info!("Wrote timings of {} frames to {}", profiler.frames().len(), path.display()),
+                Err(e) => error!("Couldn't write {}: {}", path.display(), e),
+            }
+        }
+        unsafe { self.window.make_current().ok() };
+        self.renderer.deinit();
+    }
 }
-/*
-extern crate glutin;
 
-mod support;
+// Without vsync nothing else limits how fast animations run.
+fn pace_frame(frame_start: Instant) {
+    let frame_time = Duration::new(0, 1_000_000_000 / 60);
+    let elapsed = frame_start.elapsed();
+    if elapsed < frame_time {
+        thread::sleep(frame_time - elapsed);
+    }
+}
 
-use glutin::GlContext;
+// The open windows. Kept apart from the events loop so they can be borrowed
+// by its callbacks.
+struct WindowList<'a> {
+    windows: Vec<PlaygroundWindow<'a>>,
+    // What stopped the app, if it wasn't closing the windows.
+    error: Option<PlaygroundError>,
+}
 
-fn main() {
-    let mut events_loop = glutin::EventsLoop::new();
-    let window = glutin::WindowBuilder::new().with_title("A fantastic window!");
-    let context = glutin::ContextBuilder::new();
-    let gl_window = glutin::GlWindow::new(window, context, &events_loop).unwrap();
+impl<'a> WindowList<'a> {
+    fn wants_animation(&self) -> bool {
+        self.windows.iter().any(|window| window.wants_animation())
+    }
 
-    let _ = unsafe { gl_window.make_current() };
+    // Restarts the clocks, so time spent waiting for events doesn't count as
+    // animation time.
+    fn reset_ticks(&mut self) {
+        let now = Instant::now();
+        for window in &mut self.windows {
+            window.last_tick = now;
+        }
+    }
+
+    // Returns false when the whole app should quit, which is when the main
+    // window or the last one closes.
+    fn close(&mut self, index: usize) -> bool {
+        let window = self.windows.remove(index);
+        let primary = window.primary;
+        window.deinit();
+        !primary && !self.windows.is_empty()
+    }
 
-    println!("Pixel format of the window's GL context: {:?}", gl_window.get_pixel_format());
+    // Like `close`, returns false when the app should quit.
+    fn draw(&mut self, index: usize) -> bool {
+        match self.windows[index].draw() {
+            Ok(true) => true,
+            Ok(false) => self.close(index),
+            Err(e) => {
+                self.error = Some(e);
+                false
+            }
+        }
+    }
 
-    let gl = support::load(&gl_window);
+    // Like `close`, returns false when the app should quit.
+    fn handle_event(&mut self, event: glutin::Event) -> bool {
+        trace!("{:?}", event);
 
-    events_loop.run_forever(|event| {
-        println!("{:?}", event);
         match event {
-            glutin::Event::WindowEvent { event, .. } => match event {
-                glutin::WindowEvent::Closed => return glutin::ControlFlow::Break,
-                glutin::WindowEvent::Resized(w, h) => gl_window.resize(w, h),
-                _ => (),
+            glutin::Event::WindowEvent { window_id, event } => {
+                let index = match self.windows.iter().position(|window| window.window.id() == window_id) {
+                    Some(index) => index,
+                    None => return true,
+                };
+                self.windows[index].process_file_changes();
+                if !self.windows[index].handle_window_event(event) {
+                    return self.close(index);
+                }
+                return self.draw(index);
+            }
+            // The file watchers and every window's renderer wake the loop up.
+            glutin::Event::Awakened => {
+                for index in (0..self.windows.len()).rev() {
+                    self.windows[index].process_file_changes();
+                    if self.windows[index].has_new_frame() && !self.draw(index) {
+                        return false;
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        true
+    }
+
+    // Advances and draws the windows that are animating. Returns false when
+    // the app should quit.
+    fn animate(&mut self) -> bool {
+        for index in (0..self.windows.len()).rev() {
+            if self.windows[index].wants_animation() {
+                self.windows[index].tick();
+                if !self.draw(index) {
+                    return false;
+                }
+            } else {
+                self.windows[index].last_tick = Instant::now();
+            }
+        }
+        true
+    }
+
+    fn deinit(self) {
+        for window in self.windows {
+            window.deinit();
+        }
+    }
+}
+
+/// The playground's windows and the event loop they share. Every window has
+/// its own GL context, renderer and documents, and gets the events that
+/// glutin reports for its `WindowId`.
+///
+/// The first window opened is the main one: it's the one `--record` and
+/// `--screenshot` apply to, and closing it quits.
+pub struct App<'a> {
+    cli: &'a cli::Options,
+    events_loop: glutin::EventsLoop,
+    windows: WindowList<'a>,
+}
+
+impl<'a> App<'a> {
+    pub fn new(cli: &'a cli::Options) -> App<'a> {
+        App {
+            cli,
+            events_loop: glutin::EventsLoop::new(),
+            windows: WindowList {
+                windows: Vec::new(),
+                error: None,
             },
-            _ => ()
         }
+    }
 
-        gl.draw_frame([0.0, 1.0, 0.0, 1.0]);
-        let _ = gl_window.swap_buffers();
-        glutin::ControlFlow::Continue
-    });
+    /// Opens a window showing `panes`. Options passed here only apply to
+    /// this window's renderer.
+    pub fn open_window(&mut self,
+                       title: &str,
+                       panes: Vec<Pane<'a>>,
+                       options: Option<webrender::RendererOptions>) -> Result<glutin::WindowId, PlaygroundError> {
+        let primary = self.windows.windows.is_empty();
+        let window = PlaygroundWindow::new(&self.events_loop, title, panes, options, self.cli, primary)?;
+        window.window.show();
+        let id = window.window.id();
+        self.windows.windows.push(window);
+        Ok(id)
+    }
+
+    /// Runs until the main window (or the last one) closes, or a window's
+    /// context is lost.
+    pub fn run(self) -> Result<(), PlaygroundError> {
+        let App { cli, mut events_loop, mut windows } = self;
+        info!("Press H for the key bindings");
+
+        // Sleep until something happens, unless an example is animating, in
+        // which case we poll and draw a frame per vsync instead.
+        let mut running = !windows.windows.is_empty();
+        while running {
+            if windows.wants_animation() {
+                let frame_start = Instant::now();
+                events_loop.poll_events(|event| {
+                    running &= windows.handle_event(event);
+                });
+                if running {
+                    running = windows.animate();
+                }
+                if !cli.vsync {
+                    pace_frame(frame_start);
+                }
+            } else {
+                events_loop.run_forever(|event| {
+                    if !windows.handle_event(event) {
+                        running = false;
+                        return glutin::ControlFlow::Break;
+                    }
+                    if windows.wants_animation() {
+                        windows.reset_ticks();
+                        return glutin::ControlFlow::Break;
+                    }
+                    glutin::ControlFlow::Continue
+                });
+            }
+        }
+
+        let error = windows.error.take();
+        windows.deinit();
+        match error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn main_wrapper(example: &mut Example,
+                    options: Option<webrender::RendererOptions>,
+                    cli: &cli::Options) -> Result<(), PlaygroundError>
+{
+    main_wrapper_with_panes(vec![Pane::full_window(example)], options, cli)
 }
 
-*/
+/// `main_wrapper` for a window split into several documents.
+pub fn main_wrapper_with_panes(panes: Vec<Pane>,
+                               options: Option<webrender::RendererOptions>,
+                               cli: &cli::Options) -> Result<(), PlaygroundError>
+{
+    let mut app = App::new(cli);
+    app.open_window("WebRender Playground", panes, options)?;
+    app.run()
+}
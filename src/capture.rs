@@ -0,0 +1,214 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Recording of everything the boilerplate sends to WebRender, so a frame
+//! sequence can be replayed without the example that produced it. See
+//! `src/bin/replay.rs`.
+
+use bincode;
+use error::PlaygroundError;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use webrender::api::*;
+
+const MAGIC: &'static [u8; 8] = b"WRPGREC2";
+
+#[derive(Deserialize, Serialize)]
+pub enum RecordedMsg {
+    SetRootPipeline(PipelineId),
+    DisplayList {
+        epoch: Epoch,
+        background: Option<ColorF>,
+        viewport_size: LayoutSize,
+        pipeline_id: PipelineId,
+        content_size: LayoutSize,
+        data: Vec<u8>,
+        descriptor: BuiltDisplayListDescriptor,
+        resources: ResourceUpdates,
+    },
+    WindowParameters {
+        size: DeviceUintSize,
+        inner_rect: DeviceUintRect,
+    },
+    /// Scroll offsets of every scroll frame, recorded whenever they changed
+    /// since the previous frame.
+    ScrollOffsets(Vec<(ClipId, LayoutVector2D)>),
+    /// With the values of animated properties, if there are any.
+    GenerateFrame(Option<DynamicProperties>),
+}
+
+/// Streams messages to a file as they happen, so a recording survives the
+/// playground crashing.
+pub struct Recorder {
+    writer: BufWriter<File>,
+    scroll_offsets: Vec<(ClipId, LayoutVector2D)>,
+    // Set once a write failed. Everything after a lost message would be
+    // useless, so nothing more is written.
+    failed: bool,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> io::Result<Recorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        Ok(Recorder {
+            writer,
+            scroll_offsets: Vec::new(),
+            failed: false,
+        })
+    }
+
+    fn write(&mut self, msg: &RecordedMsg) {
+        if self.failed {
+            return;
+        }
+        if let Err(e) = bincode::serialize_into(&mut self.writer, msg, bincode::Infinite) {
+            error!("Couldn't write to the recording, it ends here: {}", e);
+            self.failed = true;
+        }
+    }
+
+    pub fn set_root_pipeline(&mut self, pipeline_id: PipelineId) {
+        self.write(&RecordedMsg::SetRootPipeline(pipeline_id));
+    }
+
+    pub fn display_list(&mut self,
+                        epoch: Epoch,
+                        background: Option<ColorF>,
+                        viewport_size: LayoutSize,
+                        display_list: &(PipelineId, LayoutSize, BuiltDisplayList),
+                        resources: &ResourceUpdates) {
+        let (pipeline_id, content_size, ref built) = *display_list;
+        self.write(&RecordedMsg::DisplayList {
+            epoch,
+            background,
+            viewport_size,
+            pipeline_id,
+            content_size,
+            data: built.data().to_vec(),
+            descriptor: built.descriptor().clone(),
+            resources: without_external_images(resources),
+        });
+    }
+
+    pub fn window_parameters(&mut self, size: DeviceUintSize, inner_rect: DeviceUintRect) {
+        self.write(&RecordedMsg::WindowParameters { size, inner_rect });
+    }
+
+    /// Records the current scroll offsets of the document if they changed.
+    /// Scrolling produces frames without a `generate_frame` from us, so this
+    /// ends the frame as well.
+    pub fn scroll_state(&mut self, api: &RenderApi, document_id: DocumentId) {
+        let offsets: Vec<_> = api.get_scroll_node_state(document_id)
+            .into_iter()
+            .map(|state| (state.id, state.scroll_offset))
+            .collect();
+        if offsets != self.scroll_offsets {
+            self.write(&RecordedMsg::ScrollOffsets(offsets.clone()));
+            self.scroll_offsets = offsets;
+            self.generate_frame(None);
+        }
+    }
+
+    pub fn generate_frame(&mut self, properties: Option<DynamicProperties>) {
+        self.write(&RecordedMsg::GenerateFrame(properties));
+        if !self.failed {
+            self.writer.flush().ok();
+        }
+    }
+}
+
+// Replay has no handler to lock external images with, so they're recorded
+// as grey images of the same size instead.
+fn without_external_images(resources: &ResourceUpdates) -> ResourceUpdates {
+    let mut resources = resources.clone();
+    for update in &mut resources.updates {
+        match *update {
+            ResourceUpdate::AddImage(ref mut image) => {
+                replace_external_image(&mut image.descriptor, &mut image.data);
+            }
+            ResourceUpdate::UpdateImage(ref mut image) => {
+                replace_external_image(&mut image.descriptor, &mut image.data);
+            }
+            _ => (),
+        }
+    }
+    resources
+}
+
+fn replace_external_image(descriptor: &mut ImageDescriptor, data: &mut ImageData) {
+    if let ImageData::External(..) = *data {
+        *descriptor = ImageDescriptor { stride: None, offset: 0, .. *descriptor };
+        let size = descriptor.width * descriptor.height * descriptor.format.bytes_per_pixel();
+        *data = ImageData::new(vec![0x80; size as usize]);
+    }
+}
+
+pub struct Replay {
+    reader: BufReader<File>,
+}
+
+impl Replay {
+    pub fn open(path: &Path) -> io::Result<Replay> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a playground recording"));
+        }
+        Ok(Replay {
+            reader,
+        })
+    }
+
+    /// Sends messages to `api` up to and including the next `GenerateFrame`.
+    /// Returns false once the recording is exhausted. Running out in the
+    /// middle of a message means the recording was cut short, which is an
+    /// error.
+    pub fn play_frame(&mut self, api: &RenderApi, document_id: DocumentId) -> Result<bool, PlaygroundError> {
+        loop {
+            let at_end = self.reader.fill_buf()
+                .map_err(|e| PlaygroundError::BadRecording(Box::new(bincode::ErrorKind::Io(e))))?
+                .is_empty();
+            if at_end {
+                return Ok(false);
+            }
+            let msg: RecordedMsg = bincode::deserialize_from(&mut self.reader, bincode::Infinite)
+                .map_err(PlaygroundError::BadRecording)?;
+            match msg {
+                RecordedMsg::SetRootPipeline(pipeline_id) => {
+                    api.set_root_pipeline(document_id, pipeline_id);
+                }
+                RecordedMsg::DisplayList { epoch, background, viewport_size, pipeline_id,
+                                           content_size, data, descriptor, resources } => {
+                    let built = BuiltDisplayList::from_data(data, descriptor);
+                    api.set_display_list(document_id,
+                                         epoch,
+                                         background,
+                                         viewport_size,
+                                         (pipeline_id, content_size, built),
+                                         true,
+                                         resources);
+                }
+                RecordedMsg::WindowParameters { size, inner_rect } => {
+                    api.set_window_parameters(document_id, size, inner_rect);
+                }
+                RecordedMsg::ScrollOffsets(offsets) => {
+                    for (id, offset) in offsets {
+                        // WebRender reports offsets, but scrolls to origins.
+                        api.scroll_node_with_id(document_id,
+                                                LayoutPoint::new(-offset.x, -offset.y),
+                                                id,
+                                                ScrollClamping::NoClamping);
+                    }
+                }
+                RecordedMsg::GenerateFrame(properties) => {
+                    api.generate_frame(document_id, properties);
+                    return Ok(true);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,457 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use bench::{self, StressScene};
+use boilerplate::parse_debug_flags;
+use clap::{self, App, Arg, ArgMatches};
+use glutin;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::str::FromStr;
+use webrender;
+use webrender::api::DeviceUintSize;
+use webrender::renderer::DebugFlags;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlApi {
+    /// Desktop GL if available, GLES otherwise.
+    Auto,
+    Gl,
+    Gles,
+}
+
+/// Everything that can be set from the command line.
+pub struct Options {
+    pub example: String,
+    pub list: bool,
+    pub scene: Option<PathBuf>,
+    /// Examples for a second document on the right half of the window, and
+    /// for an iframe inside the main example.
+    pub split: Option<String>,
+    pub iframe: Option<String>,
+    /// An example for a second window.
+    pub second_window: Option<String>,
+    pub resource_path: Option<PathBuf>,
+    /// `None` lets the window system pick.
+    pub window_size: Option<DeviceUintSize>,
+    /// `None` uses the window's hidpi factor, or 1 when headless.
+    pub device_pixel_ratio: Option<f32>,
+    pub gl_api: GlApi,
+    /// GL versions to try, best first. See `context::context_configs`.
+    pub gl_versions: Vec<(glutin::Api, (u8, u8))>,
+    pub gl_profiles: Vec<glutin::GlProfile>,
+    /// Samples per pixel, 0 for none.
+    pub multisampling: u16,
+    pub srgb: bool,
+    pub vsync: bool,
+    pub precache_shaders: bool,
+    pub debug_flags: DebugFlags,
+    pub headless: bool,
+    pub frames: usize,
+    pub output_dir: PathBuf,
+    pub screenshot: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    /// CSV, or a Chrome trace when it ends in `.json`.
+    pub profile: Option<PathBuf>,
+    pub reftest: bool,
+    /// In env_logger's syntax, see `logging::init`.
+    pub log_filter: Option<String>,
+    pub log_json: Option<PathBuf>,
+    pub bless: bool,
+    pub tolerance: u8,
+    pub bench: bool,
+    pub bench_scenes: Vec<StressScene>,
+    pub bench_frames: usize,
+    pub bench_warmup: usize,
+    pub bench_baseline: Option<PathBuf>,
+    pub bench_save: Option<PathBuf>,
+    /// In percent.
+    pub bench_threshold: f64,
+}
+
+pub const DEFAULT_HEADLESS_SIZE: (u32, u32) = (1024, 768);
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("webrender_playground")
+        .about("Renders WebRender examples in a window, headlessly, or as reftests")
+        .arg(Arg::with_name("example")
+             .value_name("EXAMPLE")
+             .default_value("grid")
+             .help("The example to run, see --list"))
+        .arg(Arg::with_name("list")
+             .long("list")
+             .help("List the available examples"))
+        .arg(Arg::with_name("scene")
+             .long("scene")
+             .value_name("PATH")
+             .help("Render a YAML scene file instead of an example"))
+        .arg(Arg::with_name("split")
+             .long("split")
+             .value_name("EXAMPLE")
+             .conflicts_with("headless")
+             .help("Show EXAMPLE in its own document on the right half of the window"))
+        .arg(Arg::with_name("iframe")
+             .long("iframe")
+             .value_name("EXAMPLE")
+             .conflicts_with("headless")
+             .help("Embed EXAMPLE in the main example as an iframe"))
+        .arg(Arg::with_name("window")
+             .long("window")
+             .value_name("EXAMPLE")
+             .conflicts_with("headless")
+             .help("Open EXAMPLE in a second window"))
+        .arg(Arg::with_name("shaders")
+             .long("shaders")
+             .value_name("DIR")
+             .help("Load WebRender's shaders from DIR instead of the built-in ones"))
+        .arg(Arg::with_name("size")
+             .long("size")
+             .value_name("WxH")
+             .help("Window (or headless output) size in device pixels [headless default: 1024x768]"))
+        .arg(Arg::with_name("dpr")
+             .long("dpr")
+             .value_name("RATIO")
+             .help("Override the device pixel ratio [default: the window's hidpi factor, 1 headless]"))
+        .arg(Arg::with_name("gl")
+             .long("gl")
+             .value_name("API")
+             .possible_values(&["auto", "gl", "gles"])
+             .default_value("auto")
+             .help("Which flavour of OpenGL to ask for"))
+        .arg(Arg::with_name("gl_versions")
+             .long("gl-versions")
+             .value_name("LIST")
+             .help("GL versions to try in order, e.g. gl3.3,gl3.2,gles3.0 [default: depends on --gl]"))
+        .arg(Arg::with_name("gl_profiles")
+             .long("gl-profiles")
+             .value_name("LIST")
+             .default_value("core,compat")
+             .help("Desktop GL profiles to try in order, core and/or compat"))
+        .arg(Arg::with_name("msaa")
+             .long("msaa")
+             .value_name("SAMPLES")
+             .default_value("0")
+             .help("Ask for a multisampled window with 2, 4, 8... samples, falling back to none"))
+        .arg(Arg::with_name("srgb")
+             .long("srgb")
+             .help("Ask for an sRGB framebuffer, falling back to a plain one"))
+        .arg(Arg::with_name("vsync")
+             .long("vsync")
+             .help("Synchronize buffer swaps with the display"))
+        .arg(Arg::with_name("precache_shaders")
+             .long("precache-shaders")
+             .help("Compile all shaders at startup instead of on first use"))
+        .arg(Arg::with_name("debug_flags")
+             .long("debug-flags")
+             .value_name("FLAGS")
+             .help("Comma separated debug views to enable: profiler, render-targets, texture-cache"))
+        .arg(Arg::with_name("headless")
+             .long("headless")
+             .help("Render offscreen without opening a window, then exit"))
+        .arg(Arg::with_name("frames")
+             .long("frames")
+             .value_name("N")
+             .default_value("1")
+             .help("Number of frames to render when headless"))
+        .arg(Arg::with_name("output_dir")
+             .long("output-dir")
+             .value_name("DIR")
+             .default_value(".")
+             .help("Where screenshots and reftest failures are written"))
+        .arg(Arg::with_name("screenshot")
+             .long("screenshot")
+             .value_name("PATH")
+             .help("Save the first frame (the last one when headless) as a PNG and exit"))
+        .arg(Arg::with_name("record")
+             .long("record")
+             .value_name("PATH")
+             .help("Record everything sent to WebRender, for the replay binary"))
+        .arg(Arg::with_name("profile_out")
+             .long("profile-out")
+             .value_name("PATH")
+             .help("Write per-frame timings to PATH on exit, as a Chrome trace if it ends in .json \
+                    and as CSV otherwise"))
+        .arg(Arg::with_name("log")
+             .long("log")
+             .value_name("FILTER")
+             .help("What to log to stderr, e.g. info or webrender_playground::boilerplate=trace \
+                    [default: $RUST_LOG, or warn with info for the playground]"))
+        .arg(Arg::with_name("log_json")
+             .long("log-json")
+             .value_name("PATH")
+             .help("Also write log messages to PATH as JSON, one object per line"))
+        .arg(Arg::with_name("reftest")
+             .long("reftest")
+             .help("Run the reftests instead of an example"))
+        .arg(Arg::with_name("bless")
+             .long("bless")
+             .requires("reftest")
+             .help("Overwrite the reftest references with the current output"))
+        .arg(Arg::with_name("tolerance")
+             .long("tolerance")
+             .value_name("N")
+             .default_value("2")
+             .help("Largest per-channel difference the reftests accept"))
+        .arg(Arg::with_name("bench")
+             .long("bench")
+             .conflicts_with("reftest")
+             .help("Run the benchmarks instead of an example"))
+        .arg(Arg::with_name("bench_scenes")
+             .long("bench-scenes")
+             .value_name("LIST")
+             .default_value(bench::DEFAULT_SCENES)
+             .help("Comma separated scenes to benchmark: grid:COLUMNSxROWS, stacking:DEPTH, \
+                    clips:N, scroll:N, text:CHARS"))
+        .arg(Arg::with_name("bench_frames")
+             .long("bench-frames")
+             .value_name("N")
+             .default_value("100")
+             .help("Frames to measure per benchmark scene"))
+        .arg(Arg::with_name("bench_warmup")
+             .long("bench-warmup")
+             .value_name("N")
+             .default_value("10")
+             .help("Frames to render before measuring each benchmark scene"))
+        .arg(Arg::with_name("bench_baseline")
+             .long("bench-baseline")
+             .value_name("PATH")
+             .requires("bench")
+             .help("Compare the benchmark results with ones saved by --bench-save"))
+        .arg(Arg::with_name("bench_save")
+             .long("bench-save")
+             .value_name("PATH")
+             .requires("bench")
+             .help("Save the benchmark results as a baseline"))
+        .arg(Arg::with_name("bench_threshold")
+             .long("bench-threshold")
+             .value_name("PERCENT")
+             .default_value("10")
+             .help("How much slower than the baseline a benchmark may get before failing"))
+}
+
+fn parse<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<Option<T>, String> {
+    match matches.value_of(name) {
+        Some(value) => value.parse()
+            .map(Some)
+            .map_err(|_| format!("invalid value for --{}: {:?}", name.replace('_', "-"), value)),
+        None => Ok(None),
+    }
+}
+
+fn parse_gl_version(version: &str) -> Result<(glutin::Api, (u8, u8)), String> {
+    let (api, number) = if version.starts_with("gles") {
+        (glutin::Api::OpenGlEs, &version[4..])
+    } else if version.starts_with("gl") {
+        (glutin::Api::OpenGl, &version[2..])
+    } else {
+        return Err(format!("invalid GL version {:?}, expected e.g. gl3.2 or gles3.0", version));
+    };
+    let mut parts = number.split('.').map(|part| part.parse::<u8>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor)), None) => Ok((api, (major, minor))),
+        _ => Err(format!("invalid GL version {:?}, expected e.g. gl3.2 or gles3.0", version)),
+    }
+}
+
+fn parse_gl_profile(profile: &str) -> Result<glutin::GlProfile, String> {
+    match profile {
+        "core" => Ok(glutin::GlProfile::Core),
+        "compat" => Ok(glutin::GlProfile::Compatibility),
+        _ => Err(format!("unknown GL profile {:?}, expected core or compat", profile)),
+    }
+}
+
+fn parse_list<T, F>(list: &str, parse: F) -> Result<Vec<T>, String>
+    where F: Fn(&str) -> Result<T, String>
+{
+    list.split(',').map(|item| item.trim()).filter(|item| !item.is_empty()).map(parse).collect()
+}
+
+fn parse_size(size: &str) -> Result<DeviceUintSize, String> {
+    let mut parts = size.split('x').map(|part| part.parse::<u32>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(width)), Some(Ok(height)), None) => Ok(DeviceUintSize::new(width, height)),
+        _ => Err(format!("invalid size {:?}, expected WIDTHxHEIGHT", size)),
+    }
+}
+
+impl Default for Options {
+    /// The options when nothing is passed on the command line.
+    fn default() -> Options {
+        // Every argument is optional, so no arguments always parse.
+        Options::from_iter(vec!["webrender_playground"]).unwrap()
+    }
+}
+
+impl Options {
+    /// Parses the process arguments, printing usage and exiting on errors
+    /// and for `--help`.
+    pub fn from_args() -> Options {
+        let matches = app().get_matches();
+        Options::from_matches(&matches).unwrap_or_else(|e| {
+            clap::Error::with_description(&e, clap::ErrorKind::InvalidValue).exit()
+        })
+    }
+
+    pub fn from_iter<I, T>(args: I) -> Result<Options, String>
+        where I: IntoIterator<Item = T>, T: Into<OsString> + Clone
+    {
+        let matches = app().get_matches_from_safe(args).map_err(|e| e.message)?;
+        Options::from_matches(&matches)
+    }
+
+    // Arguments with a default value are always present, so unwrapping
+    // their values can't fail.
+    fn from_matches(matches: &ArgMatches) -> Result<Options, String> {
+        let window_size = match matches.value_of("size") {
+            Some(size) => Some(parse_size(size)?),
+            None => None,
+        };
+        let debug_flags = match matches.value_of("debug_flags") {
+            Some(names) => parse_debug_flags(names)?,
+            None => DebugFlags::empty(),
+        };
+        let gl_api = match matches.value_of("gl") {
+            Some("gl") => GlApi::Gl,
+            Some("gles") => GlApi::Gles,
+            _ => GlApi::Auto,
+        };
+        // WebRender needs at least GL 3.2 or GLES 3.
+        let gl_versions = match matches.value_of("gl_versions") {
+            Some(list) => parse_list(list, parse_gl_version)?,
+            None => {
+                let gl = vec![(glutin::Api::OpenGl, (3, 3)), (glutin::Api::OpenGl, (3, 2))];
+                let gles = vec![(glutin::Api::OpenGlEs, (3, 0))];
+                match gl_api {
+                    GlApi::Auto => gl.into_iter().chain(gles).collect(),
+                    GlApi::Gl => gl,
+                    GlApi::Gles => gles,
+                }
+            }
+        };
+        if gl_versions.is_empty() {
+            return Err("--gl-versions needs at least one version".to_string());
+        }
+        let gl_profiles = parse_list(matches.value_of("gl_profiles").unwrap(), parse_gl_profile)?;
+        if gl_profiles.is_empty() {
+            return Err("--gl-profiles needs at least one profile".to_string());
+        }
+        // glutin asserts on anything else.
+        let multisampling: u16 = parse(matches, "msaa")?.unwrap();
+        if multisampling != 0 && !multisampling.is_power_of_two() {
+            return Err(format!("invalid value for --msaa: {}, expected 0 or a power of two", multisampling));
+        }
+        let device_pixel_ratio: Option<f32> = parse(matches, "dpr")?;
+        if let Some(dpr) = device_pixel_ratio {
+            if !dpr.is_finite() || dpr <= 0.0 {
+                return Err(format!("invalid value for --dpr: {}, expected a positive number", dpr));
+            }
+        }
+
+        Ok(Options {
+            example: matches.value_of("example").unwrap().to_string(),
+            list: matches.is_present("list"),
+            scene: matches.value_of("scene").map(PathBuf::from),
+            split: matches.value_of("split").map(String::from),
+            iframe: matches.value_of("iframe").map(String::from),
+            second_window: matches.value_of("window").map(String::from),
+            resource_path: matches.value_of("shaders").map(PathBuf::from),
+            window_size,
+            device_pixel_ratio,
+            gl_api,
+            gl_versions,
+            gl_profiles,
+            multisampling,
+            srgb: matches.is_present("srgb"),
+            vsync: matches.is_present("vsync"),
+            precache_shaders: matches.is_present("precache_shaders"),
+            debug_flags,
+            headless: matches.is_present("headless"),
+            frames: parse(matches, "frames")?.unwrap(),
+            output_dir: PathBuf::from(matches.value_of("output_dir").unwrap()),
+            screenshot: matches.value_of("screenshot").map(PathBuf::from),
+            record: matches.value_of("record").map(PathBuf::from),
+            profile: matches.value_of("profile_out").map(PathBuf::from),
+            reftest: matches.is_present("reftest"),
+            log_filter: matches.value_of("log").map(String::from),
+            log_json: matches.value_of("log_json").map(PathBuf::from),
+            bless: matches.is_present("bless"),
+            tolerance: parse(matches, "tolerance")?.unwrap(),
+            bench: matches.is_present("bench"),
+            bench_scenes: parse_list(matches.value_of("bench_scenes").unwrap(), bench::parse_scene)?,
+            bench_frames: parse(matches, "bench_frames")?.unwrap(),
+            bench_warmup: parse(matches, "bench_warmup")?.unwrap(),
+            bench_baseline: matches.value_of("bench_baseline").map(PathBuf::from),
+            bench_save: matches.value_of("bench_save").map(PathBuf::from),
+            bench_threshold: parse(matches, "bench_threshold")?.unwrap(),
+        })
+    }
+
+    pub fn headless_size(&self) -> DeviceUintSize {
+        self.window_size.unwrap_or(DeviceUintSize::new(DEFAULT_HEADLESS_SIZE.0,
+                                                       DEFAULT_HEADLESS_SIZE.1))
+    }
+
+    /// The options from the command line applied on top of `base`.
+    pub fn renderer_options(&self,
+                            device_pixel_ratio: f32,
+                            base: Option<webrender::RendererOptions>) -> webrender::RendererOptions {
+        webrender::RendererOptions {
+            resource_override_path: self.resource_path.clone(),
+            debug: true,
+            precache_shaders: self.precache_shaders,
+            device_pixel_ratio,
+            .. base.unwrap_or(webrender::RendererOptions::default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gl_versions_parse() {
+        assert_eq!(parse_gl_version("gl3.2"), Ok((glutin::Api::OpenGl, (3, 2))));
+        assert_eq!(parse_gl_version("gles3.0"), Ok((glutin::Api::OpenGlEs, (3, 0))));
+        assert!(parse_gl_version("3.2").is_err());
+        assert!(parse_gl_version("gl3").is_err());
+        assert!(parse_gl_version("gl3.2.1").is_err());
+        assert!(parse_gl_version("gles3.x").is_err());
+    }
+
+    #[test]
+    fn gl_profiles_parse() {
+        assert_eq!(parse_gl_profile("core"), Ok(glutin::GlProfile::Core));
+        assert_eq!(parse_gl_profile("compat"), Ok(glutin::GlProfile::Compatibility));
+        assert!(parse_gl_profile("Core").is_err());
+        assert!(parse_gl_profile("").is_err());
+    }
+
+    #[test]
+    fn sizes_parse() {
+        assert_eq!(parse_size("800x600"), Ok(DeviceUintSize::new(800, 600)));
+        assert!(parse_size("800").is_err());
+        assert!(parse_size("800x").is_err());
+        assert!(parse_size("800x600x2").is_err());
+        assert!(parse_size("-800x600").is_err());
+    }
+
+    #[test]
+    fn lists_skip_blanks_and_stop_at_the_first_error() {
+        assert_eq!(parse_list(" core, compat,,", parse_gl_profile),
+                   Ok(vec![glutin::GlProfile::Core, glutin::GlProfile::Compatibility]));
+        assert_eq!(parse_list("", parse_gl_profile), Ok(vec![]));
+        assert!(parse_list("core,bogus", parse_gl_profile).is_err());
+    }
+
+    #[test]
+    fn dpr_must_be_positive() {
+        assert_eq!(Options::from_iter(vec!["webrender_playground", "--dpr", "2"]).unwrap().device_pixel_ratio,
+                   Some(2.0));
+        for &dpr in &["--dpr=0", "--dpr=-1", "--dpr=NaN", "--dpr=inf"] {
+            assert!(Options::from_iter(vec!["webrender_playground", dpr]).is_err());
+        }
+    }
+}
@@ -0,0 +1,146 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Which GL contexts to ask for, in what order, and what we got.
+
+use cli;
+use gleam::gl;
+use glutin::{self, GlContext};
+use std::fmt;
+use std::rc::Rc;
+
+/// One set of context settings to try.
+#[derive(Clone, Copy, Debug)]
+pub struct ContextConfig {
+    pub api: glutin::Api,
+    pub version: (u8, u8),
+    /// Only means something for desktop GL.
+    pub profile: Option<glutin::GlProfile>,
+    pub multisampling: u16,
+    pub srgb: bool,
+}
+
+impl ContextConfig {
+    pub fn request(&self) -> glutin::GlRequest {
+        glutin::GlRequest::Specific(self.api, self.version)
+    }
+
+    pub fn apply<'a>(&self, builder: glutin::ContextBuilder<'a>) -> glutin::ContextBuilder<'a> {
+        let mut builder = builder
+            .with_gl(self.request())
+            .with_multisampling(self.multisampling)
+            .with_srgb(self.srgb);
+        if let Some(profile) = self.profile {
+            builder = builder.with_gl_profile(profile);
+        }
+        builder
+    }
+
+    /// Headless contexts don't take pixel format settings.
+    pub fn apply_headless<'a>(&self, builder: glutin::HeadlessRendererBuilder<'a>) -> glutin::HeadlessRendererBuilder<'a> {
+        let builder = builder.with_gl(self.request());
+        match self.profile {
+            Some(profile) => builder.with_gl_profile(profile),
+            None => builder,
+        }
+    }
+}
+
+impl fmt::Display for ContextConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let api = match self.api {
+            glutin::Api::OpenGlEs => "GLES",
+            _ => "GL",
+        };
+        write!(f, "{} {}.{}", api, self.version.0, self.version.1)?;
+        match self.profile {
+            Some(glutin::GlProfile::Core) => write!(f, " core")?,
+            Some(glutin::GlProfile::Compatibility) => write!(f, " compatibility")?,
+            None => (),
+        }
+        if self.multisampling > 0 {
+            write!(f, ", {}x MSAA", self.multisampling)?;
+        }
+        if self.srgb {
+            write!(f, ", sRGB")?;
+        }
+        Ok(())
+    }
+}
+
+/// Every config to try, best first. Each version is tried with each
+/// profile, first with the multisampling and sRGB settings from `cli` and
+/// then without them, since those are what drivers most often refuse.
+pub fn context_configs(cli: &cli::Options) -> Vec<ContextConfig> {
+    let mut configs = Vec::new();
+    for &(api, version) in &cli.gl_versions {
+        let profiles = match api {
+            glutin::Api::OpenGlEs => vec![None],
+            _ => cli.gl_profiles.iter().cloned().map(Some).collect(),
+        };
+        for profile in profiles {
+            let config = ContextConfig {
+                api,
+                version,
+                profile,
+                multisampling: cli.multisampling,
+                srgb: cli.srgb,
+            };
+            configs.push(config);
+            if config.multisampling > 0 || config.srgb {
+                configs.push(ContextConfig { multisampling: 0, srgb: false, .. config });
+            }
+        }
+    }
+    configs
+}
+
+/// Loads GL for `context`, which must be current, going by the API it
+/// actually got rather than the one asked for.
+pub fn load_gl<C: GlContext>(context: &C) -> Rc<gl::Gl> {
+    match context.get_api() {
+        glutin::Api::OpenGlEs => unsafe { gl::GlesFns::load_with(|symbol| context.get_proc_address(symbol) as *const _) },
+        _ => unsafe { gl::GlFns::load_with(|symbol| context.get_proc_address(symbol) as *const _) },
+    }
+}
+
+/// What the driver says about a context.
+pub struct Capabilities {
+    pub api: glutin::Api,
+    pub version: String,
+    pub shading_language: String,
+    pub vendor: String,
+    pub renderer: String,
+    pub max_texture_size: i32,
+    pub extensions: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn query(gl: &gl::Gl, api: glutin::Api) -> Capabilities {
+        // `GL_EXTENSIONS` as a single string is gone from core profiles.
+        let extension_count = gl.get_integer_v(gl::NUM_EXTENSIONS).max(0) as gl::GLuint;
+        Capabilities {
+            api,
+            version: gl.get_string(gl::VERSION),
+            shading_language: gl.get_string(gl::SHADING_LANGUAGE_VERSION),
+            vendor: gl.get_string(gl::VENDOR),
+            renderer: gl.get_string(gl::RENDERER),
+            max_texture_size: gl.get_integer_v(gl::MAX_TEXTURE_SIZE),
+            extensions: (0..extension_count).map(|index| gl.get_string_i(gl::EXTENSIONS, index)).collect(),
+        }
+    }
+
+    /// Logs the capabilities, with the long extension list at debug level.
+    pub fn report(&self) {
+        info!("GL capabilities:");
+        info!("  API: {:?}", self.api);
+        info!("  Version: {}", self.version);
+        info!("  Shading language: {}", self.shading_language);
+        info!("  Vendor: {}", self.vendor);
+        info!("  Renderer: {}", self.renderer);
+        info!("  Max texture size: {}", self.max_texture_size);
+        info!("  Extensions: {}", self.extensions.len());
+        debug!("  {}", self.extensions.join(" "));
+    }
+}
@@ -0,0 +1,84 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use bincode;
+use context::ContextConfig;
+use glutin;
+use std::error;
+use std::fmt;
+use std::io;
+use webrender::renderer::InitError;
+
+/// Why the playground couldn't open a window or had to stop.
+#[derive(Debug)]
+pub enum PlaygroundError {
+    /// Every context config we tried failed, with why.
+    NoContext(Vec<(ContextConfig, glutin::CreationError)>),
+    /// The driver reset, or the GPU went away.
+    ContextLost,
+    Context(io::Error),
+    /// The window was closed before we could set it up.
+    WindowGone,
+    /// A window was opened without any panes to show.
+    NoPanes,
+    Renderer(InitError),
+    /// WebRender's backend thread stopped, most likely by panicking.
+    BackendGone,
+    Recording(io::Error),
+    /// A recording that ends in the middle of a message, or can't be decoded.
+    BadRecording(bincode::Error),
+}
+
+impl fmt::Display for PlaygroundError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PlaygroundError::NoContext(ref attempts) => {
+                write!(f, "couldn't create a GL context")?;
+                for &(ref config, ref e) in attempts {
+                    write!(f, "\n  {}: {}", config, e)?;
+                }
+                Ok(())
+            }
+            PlaygroundError::ContextLost => write!(f, "the GL context was lost"),
+            PlaygroundError::Context(ref e) => write!(f, "GL context error: {}", e),
+            PlaygroundError::WindowGone => write!(f, "the window went away while opening"),
+            PlaygroundError::NoPanes => write!(f, "a window needs at least one pane"),
+            PlaygroundError::Renderer(ref e) => write!(f, "couldn't create the renderer: {:?}", e),
+            PlaygroundError::BackendGone => write!(f, "WebRender's backend thread stopped"),
+            PlaygroundError::Recording(ref e) => write!(f, "couldn't create the recording: {}", e),
+            PlaygroundError::BadRecording(ref e) => write!(f, "the recording is truncated or corrupt: {}", e),
+        }
+    }
+}
+
+impl error::Error for PlaygroundError {
+    fn description(&self) -> &str {
+        match *self {
+            PlaygroundError::NoContext(..) => "couldn't create a GL context",
+            PlaygroundError::ContextLost => "the GL context was lost",
+            PlaygroundError::Context(..) => "GL context error",
+            PlaygroundError::WindowGone => "the window went away while opening",
+            PlaygroundError::NoPanes => "a window needs at least one pane",
+            PlaygroundError::Renderer(..) => "couldn't create the renderer",
+            PlaygroundError::BackendGone => "WebRender's backend thread stopped",
+            PlaygroundError::Recording(..) => "couldn't create the recording",
+            PlaygroundError::BadRecording(..) => "the recording is truncated or corrupt",
+        }
+    }
+}
+
+impl From<InitError> for PlaygroundError {
+    fn from(e: InitError) -> PlaygroundError {
+        PlaygroundError::Renderer(e)
+    }
+}
+
+impl From<glutin::ContextError> for PlaygroundError {
+    fn from(e: glutin::ContextError) -> PlaygroundError {
+        match e {
+            glutin::ContextError::ContextLost => PlaygroundError::ContextLost,
+            glutin::ContextError::IoError(e) => PlaygroundError::Context(e),
+        }
+    }
+}
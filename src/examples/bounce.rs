@@ -0,0 +1,98 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use boilerplate::{Example, HandyDandyRectBuilder};
+use glutin;
+use webrender::api::*;
+
+const BOX_SIZE: f32 = 100.0;
+/// In layout pixels per second.
+const INITIAL_VELOCITY: (f32, f32) = (240.0, 180.0);
+
+/// A box bouncing off the edges of the window. The display list is rebuilt
+/// on every tick, which is the simplest way to animate. Space pauses.
+pub struct Bounce {
+    position: LayoutPoint,
+    velocity: LayoutVector2D,
+    layout_size: LayoutSize,
+    paused: bool,
+}
+
+impl Bounce {
+    pub fn new() -> Bounce {
+        Bounce {
+            position: LayoutPoint::zero(),
+            velocity: LayoutVector2D::new(INITIAL_VELOCITY.0, INITIAL_VELOCITY.1),
+            layout_size: LayoutSize::zero(),
+            paused: false,
+        }
+    }
+}
+
+impl Example for Bounce {
+    fn render(&mut self,
+              _api: &RenderApi,
+              builder: &mut DisplayListBuilder,
+              _resources: &mut ResourceUpdates,
+              layout_size: LayoutSize,
+              _pipeline_id: PipelineId,
+              _document_id: DocumentId) {
+        self.layout_size = layout_size;
+
+        let bounds = LayoutRect::new(LayoutPoint::zero(), layout_size);
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      bounds,
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+        let (x, y) = (self.position.x as i32, self.position.y as i32);
+        builder.push_rect((x, y).by(BOX_SIZE as i32, BOX_SIZE as i32),
+                          None,
+                          ColorF::new(0.0, 0.6, 1.0, 1.0));
+        builder.pop_stacking_context();
+    }
+
+    fn on_event(&mut self,
+                event: glutin::WindowEvent,
+                _api: &RenderApi,
+                _document_id: DocumentId) -> bool {
+        match event {
+            glutin::WindowEvent::Resized(..) => true,
+            glutin::WindowEvent::KeyboardInput {
+                input: glutin::KeyboardInput {
+                    state: glutin::ElementState::Pressed,
+                    virtual_keycode: Some(glutin::VirtualKeyCode::Space),
+                    ..
+                },
+                ..
+            } => {
+                self.paused = !self.paused;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn wants_animation(&self) -> bool {
+        !self.paused
+    }
+
+    fn tick(&mut self, dt: f32) -> bool {
+        self.position = self.position + self.velocity * dt;
+
+        let max_x = (self.layout_size.width - BOX_SIZE).max(0.0);
+        let max_y = (self.layout_size.height - BOX_SIZE).max(0.0);
+        if self.position.x < 0.0 || self.position.x > max_x {
+            self.velocity.x = -self.velocity.x;
+            self.position.x = self.position.x.max(0.0).min(max_x);
+        }
+        if self.position.y < 0.0 || self.position.y > max_y {
+            self.velocity.y = -self.velocity.y;
+            self.position.y = self.position.y.max(0.0).min(max_y);
+        }
+        true
+    }
+}
@@ -0,0 +1,199 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use boilerplate::Example;
+use gleam::gl;
+use glutin;
+use std::rc::Rc;
+use std::time::Instant;
+use support::{self, GlError, GlTest, SavedBindings};
+use webrender::api::*;
+use webrender::renderer::{ExternalImage, ExternalImageHandler, ExternalImageSource};
+
+const TEXTURE_SIZE: u32 = 256;
+const TRIANGLE_IMAGE: ExternalImageId = ExternalImageId(0);
+
+/// Draws `support::GlTest`'s triangle into a texture whenever WebRender
+/// locks the image, over a background that changes color with time.
+struct TriangleTexture {
+    gl: Rc<gl::Gl>,
+    triangle: GlTest,
+    texture: gl::GLuint,
+    fbo: gl::GLuint,
+    start: Instant,
+}
+
+impl TriangleTexture {
+    fn new(gl: Rc<gl::Gl>) -> Result<TriangleTexture, GlError> {
+        let saved = SavedBindings::save(&*gl);
+
+        let triangle = match support::load(gl.clone()) {
+            Ok(triangle) => triangle,
+            Err(e) => {
+                saved.restore(&*gl);
+                return Err(e);
+            }
+        };
+
+        let texture = gl.gen_textures(1)[0];
+        gl.bind_texture(gl::TEXTURE_2D, texture);
+        gl.tex_parameter_i(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as gl::GLint);
+        gl.tex_parameter_i(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as gl::GLint);
+        gl.tex_parameter_i(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as gl::GLint);
+        gl.tex_parameter_i(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as gl::GLint);
+        gl.tex_image_2d(gl::TEXTURE_2D, 0, gl::RGBA as gl::GLint,
+                        TEXTURE_SIZE as gl::GLsizei, TEXTURE_SIZE as gl::GLsizei, 0,
+                        gl::RGBA, gl::UNSIGNED_BYTE, None);
+
+        let fbo = gl.gen_framebuffers(1)[0];
+        gl.bind_framebuffer(gl::FRAMEBUFFER, fbo);
+        gl.framebuffer_texture_2d(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture, 0);
+        let status = gl.check_frame_buffer_status(gl::FRAMEBUFFER);
+
+        saved.restore(&*gl);
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            gl.delete_framebuffers(&[fbo]);
+            gl.delete_textures(&[texture]);
+            return Err(GlError::IncompleteFramebuffer(status));
+        }
+
+        Ok(TriangleTexture {
+            gl,
+            triangle,
+            texture,
+            fbo,
+            start: Instant::now(),
+        })
+    }
+
+    fn draw(&mut self) {
+        let elapsed = self.start.elapsed();
+        let t = elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 * 1e-9;
+        let color = [0.5 + 0.5 * t.sin(), 0.5 + 0.5 * (t * 0.7).cos(), 0.5, 1.0];
+
+        let saved = SavedBindings::save(&*self.gl);
+        // WebRender sets the viewport and these for each of its own draws.
+        self.gl.disable(gl::SCISSOR_TEST);
+        self.gl.disable(gl::DEPTH_TEST);
+        self.gl.disable(gl::BLEND);
+        self.gl.bind_framebuffer(gl::FRAMEBUFFER, self.fbo);
+        self.gl.viewport(0, 0, TEXTURE_SIZE as gl::GLint, TEXTURE_SIZE as gl::GLint);
+        self.triangle.draw_frame(color);
+        saved.restore(&*self.gl);
+    }
+}
+
+impl ExternalImageHandler for TriangleTexture {
+    fn lock(&mut self, _key: ExternalImageId, _channel_index: u8) -> ExternalImage {
+        self.draw();
+        // GL's rows go bottom to top.
+        ExternalImage {
+            u0: 0.0,
+            v0: 1.0,
+            u1: 1.0,
+            v1: 0.0,
+            source: ExternalImageSource::NativeTexture(self.texture),
+        }
+    }
+
+    fn unlock(&mut self, _key: ExternalImageId, _channel_index: u8) {
+    }
+}
+
+impl Drop for TriangleTexture {
+    fn drop(&mut self) {
+        self.gl.delete_framebuffers(&[self.fbo]);
+        self.gl.delete_textures(&[self.texture]);
+    }
+}
+
+/// A texture we draw into with plain GL, composited by WebRender like any
+/// other image. WebRender only asks for external images when it builds a
+/// frame, so the display list is rebuilt on every tick to keep it moving.
+pub struct External {
+    image_key: Option<ImageKey>,
+}
+
+impl External {
+    pub fn new() -> External {
+        External {
+            image_key: None,
+        }
+    }
+}
+
+impl Example for External {
+    fn render(&mut self,
+              api: &RenderApi,
+              builder: &mut DisplayListBuilder,
+              resources: &mut ResourceUpdates,
+              layout_size: LayoutSize,
+              _pipeline_id: PipelineId,
+              _document_id: DocumentId) {
+        // Keys belong to the `RenderApi` that made them, and the shader
+        // reload replaces that.
+        let image_key = match self.image_key {
+            Some(key) if key.0 == api.get_namespace_id() => key,
+            _ => {
+                let key = api.generate_image_key();
+                let descriptor = ImageDescriptor::new(TEXTURE_SIZE, TEXTURE_SIZE, ImageFormat::BGRA8, true);
+                let data = ImageData::External(ExternalImageData {
+                    id: TRIANGLE_IMAGE,
+                    channel_index: 0,
+                    image_type: ExternalImageType::Texture2DHandle,
+                });
+                resources.add_image(key, descriptor, data, None);
+                self.image_key = Some(key);
+                key
+            }
+        };
+
+        let bounds = LayoutRect::new(LayoutPoint::zero(), layout_size);
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      bounds,
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+
+        let size = LayoutSize::new(TEXTURE_SIZE as f32, TEXTURE_SIZE as f32);
+        let natural = LayoutRect::new(LayoutPoint::new(50.0, 50.0), size);
+        builder.push_image(natural, None, size, LayoutSize::zero(), ImageRendering::Auto, image_key);
+
+        let stretched = LayoutRect::new(LayoutPoint::new(350.0, 50.0), size * 2.0);
+        builder.push_image(stretched, None, stretched.size, LayoutSize::zero(),
+                           ImageRendering::Auto, image_key);
+
+        builder.pop_stacking_context();
+    }
+
+    fn on_event(&mut self,
+                event: glutin::WindowEvent,
+                _api: &RenderApi,
+                _document_id: DocumentId) -> bool {
+        match event {
+            glutin::WindowEvent::Resized(..) => true,
+            _ => false,
+        }
+    }
+
+    fn get_external_image_handler(&self, gl: &Rc<gl::Gl>) -> Option<Box<ExternalImageHandler>> {
+        match TriangleTexture::new(gl.clone()) {
+            Ok(handler) => Some(Box::new(handler)),
+            Err(e) => {
+                warn!("The external example can't draw its texture: {}", e);
+                None
+            }
+        }
+    }
+
+    fn wants_animation(&self) -> bool {
+        true
+    }
+
+    fn tick(&mut self, _dt: f32) -> bool {
+        true
+    }
+}
@@ -0,0 +1,255 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use boilerplate::{Example, HandyDandyRectBuilder};
+use glutin;
+use hit_test::{HitTag, HitTester};
+use std::mem;
+use webrender::api::*;
+use widgets::{Content, Layout, NodeId, Style, WidgetTree};
+
+const GRID_ROWS: usize = 10;
+const GRID_COLS: usize = 10;
+
+// Cells are tagged with their index, the scroll frames with this so the
+// cells they cover don't react.
+const SCROLLBOX_TAG: HitTag = (GRID_ROWS * GRID_COLS) as HitTag;
+
+/// A grid of white rects labelled with their row and column, with some
+/// nested scroll frames on top. Hovering a cell highlights it, clicking one
+/// selects it.
+pub struct Grid {
+    cursor_position: WorldPoint,
+    tree: WidgetTree,
+    // Indexed by tag.
+    cells: Vec<NodeId>,
+    hovered: Option<HitTag>,
+    selected: Option<HitTag>,
+}
+
+impl Grid {
+    pub fn new() -> Grid {
+        let mut tree = WidgetTree::new(Style {
+            layout: Layout::Grid { columns: GRID_COLS },
+            padding: 10.0,
+            gap: 10.0,
+            .. Style::default()
+        });
+        let root = tree.root();
+        let mut cells = Vec::with_capacity(GRID_ROWS * GRID_COLS);
+        for r in 0..GRID_ROWS {
+            for c in 0..GRID_COLS {
+                let label = format!("{},{}", r, c);
+                let cell = tree.add(root,
+                                    Style { padding: 4.0, .. Style::default() },
+                                    Content::text(&label, 12.0, ColorF::new(0.0, 0.0, 0.0, 1.0)));
+                tree.set_tag(cell, Some(cells.len() as HitTag));
+                cells.push(cell);
+            }
+        }
+
+        let mut grid = Grid {
+            cursor_position: WorldPoint::zero(),
+            tree,
+            cells,
+            hovered: None,
+            selected: None,
+        };
+        for tag in 0..grid.cells.len() {
+            grid.update_cell(tag as HitTag);
+        }
+        grid
+    }
+
+    fn update_cell(&mut self, tag: HitTag) {
+        let color = if self.selected == Some(tag) {
+            ColorF::new(1.0, 0.8, 0.2, 1.0)
+        } else if self.hovered == Some(tag) {
+            ColorF::new(0.8, 0.9, 1.0, 1.0)
+        } else {
+            ColorF::new(1.0, 1.0, 1.0, 1.0)
+        };
+        let cell = self.cells[tag as usize];
+        let style = Style { background: Some(color), .. *self.tree.style(cell) };
+        self.tree.set_style(cell, style);
+    }
+}
+
+impl Example for Grid {
+    fn render(&mut self,
+              api: &RenderApi,
+              builder: &mut DisplayListBuilder,
+              resources: &mut ResourceUpdates,
+              layout_size: LayoutSize,
+              _pipeline_id: PipelineId,
+              _document_id: DocumentId) {
+        debug!("rendering at size {:?}", layout_size);
+        let bounds = LayoutRect::new(LayoutPoint::zero(), layout_size);
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      bounds,
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+
+        self.tree.build(api, builder, resources, layout_size);
+
+        // let's make a scrollbox
+        let scrollbox = (0, 0).to(300, 400);
+        self.tree.hit_tester_mut().tag(scrollbox.translate(&LayoutVector2D::new(10.0, 10.0)), SCROLLBOX_TAG);
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      LayoutRect::new(LayoutPoint::new(10.0, 10.0),
+                                                      LayoutSize::zero()),
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+        // set the scrolling clip
+        let clip_id = builder.define_scroll_frame(None,
+                                                  (0, 0).by(1000, 1000),
+                                                  scrollbox,
+                                                  vec![],
+                                                  None,
+                                                  ScrollSensitivity::ScriptAndInputEvents);
+        builder.push_clip_id(clip_id);
+
+        // now put some content into it.
+        // start with a white background
+        builder.push_rect((0, 0).to(1000, 1000), None, ColorF::new(1.0, 1.0, 1.0, 1.0));
+
+        // let's make a 50x50 blue square as a visual reference
+        builder.push_rect((0, 0).to(50, 50), None, ColorF::new(0.0, 0.0, 1.0, 1.0));
+
+        // and a 50x50 green square next to it with an offset clip
+        // to see what that looks like
+        builder.push_rect((50, 0).to(100, 50),
+                          Some(LocalClip::from((60, 10).to(110, 60))),
+                          ColorF::new(0.0, 1.0, 0.0, 1.0));
+
+        // Below the above rectangles, set up a nested scrollbox. It's still in
+        // the same stacking context, so note that the rects passed in need to
+        // be relative to the stacking context.
+        let nested_clip_id = builder.define_scroll_frame(None,
+                                                         (0, 100).to(300, 400),
+                                                         (0, 100).to(200, 300),
+                                                         vec![],
+                                                         None,
+                                                         ScrollSensitivity::ScriptAndInputEvents);
+        builder.push_clip_id(nested_clip_id);
+
+        // give it a giant gray background just to distinguish it and to easily
+        // visually identify the nested scrollbox
+        builder.push_rect((-1000, -1000).to(5000, 5000), None, ColorF::new(0.5, 0.5, 0.5, 1.0));
+
+        // add a teal square to visualize the scrolling/clipping behaviour
+        // as you scroll the nested scrollbox with the arrow keys, the mouse
+        // wheel or a touchpad while the cursor is over it
+        builder.push_rect((0, 100).to(50, 150), None, ColorF::new(0.0, 1.0, 1.0, 1.0));
+
+        // just for good measure add another teal square in the bottom-right
+        // corner of the nested scrollframe content, which can be scrolled into
+        // view by the user
+        builder.push_rect((250, 350).to(300, 400), None, ColorF::new(0.0, 1.0, 1.0, 1.0));
+
+        builder.pop_clip_id(); // nested_clip_id
+
+        builder.pop_clip_id(); // clip_id
+        builder.pop_stacking_context();
+
+        builder.pop_stacking_context();
+    }
+
+    fn on_event(&mut self,
+                event: glutin::WindowEvent,
+                api: &RenderApi,
+                document_id: DocumentId) -> bool {
+        match event {
+            glutin::WindowEvent::Resized(..) => return true,
+            glutin::WindowEvent::KeyboardInput {
+                input: glutin::KeyboardInput {
+                    state: glutin::ElementState::Pressed,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+                ..
+            } => {
+                let offset = match key {
+                     glutin::VirtualKeyCode::Down => (0.0, -10.0),
+                     glutin::VirtualKeyCode::Up => (0.0, 10.0),
+                     glutin::VirtualKeyCode::Right => (-10.0, 0.0),
+                     glutin::VirtualKeyCode::Left => (10.0, 0.0),
+                     _ => return false,
+                };
+
+                api.scroll(document_id,
+                           ScrollLocation::Delta(LayoutVector2D::new(offset.0, offset.1)),
+                           self.cursor_position,
+                           ScrollEventPhase::Start);
+            }
+            glutin::WindowEvent::CursorMoved { position: (x, y), .. } => {
+                self.cursor_position = WorldPoint::new(x as f32, y as f32);
+            }
+            glutin::WindowEvent::MouseWheel { delta, phase, .. } => {
+                const LINE_HEIGHT: f32 = 38.0;
+                // Wheel clicks are self-contained, each one starts a new
+                // scroll. Touchpads send pixel deltas along with the phases
+                // of the whole gesture.
+                let (dx, dy, phase) = match delta {
+                    glutin::MouseScrollDelta::LineDelta(dx, dy) => {
+                        (dx, dy * LINE_HEIGHT, ScrollEventPhase::Start)
+                    }
+                    glutin::MouseScrollDelta::PixelDelta(dx, dy) => {
+                        let phase = match phase {
+                            glutin::TouchPhase::Started => ScrollEventPhase::Start,
+                            glutin::TouchPhase::Moved => ScrollEventPhase::Move(false),
+                            glutin::TouchPhase::Ended |
+                            glutin::TouchPhase::Cancelled => ScrollEventPhase::End,
+                        };
+                        (dx, dy, phase)
+                    }
+                };
+
+                api.scroll(document_id,
+                           ScrollLocation::Delta(LayoutVector2D::new(dx, dy)),
+                           self.cursor_position,
+                           phase);
+            }
+            _ => ()
+        }
+
+        false
+    }
+
+    fn hit_tester(&self) -> Option<&HitTester> {
+        Some(self.tree.hit_tester())
+    }
+
+    fn on_hover(&mut self, tag: Option<HitTag>) -> bool {
+        let hovered = match tag {
+            Some(SCROLLBOX_TAG) => None,
+            tag => tag,
+        };
+        let previous = mem::replace(&mut self.hovered, hovered);
+        for tag in previous.into_iter().chain(hovered) {
+            self.update_cell(tag);
+        }
+        self.tree.needs_rebuild()
+    }
+
+    fn on_click(&mut self, tag: HitTag) -> bool {
+        if tag == SCROLLBOX_TAG {
+            return false;
+        }
+        // Clicking the selected cell again deselects it.
+        let selected = if self.selected == Some(tag) { None } else { Some(tag) };
+        let previous = mem::replace(&mut self.selected, selected);
+        for tag in previous.into_iter().chain(selected) {
+            self.update_cell(tag);
+        }
+        info!("Selected cell {:?}", selected.map(|tag| (tag as usize / GRID_COLS, tag as usize % GRID_COLS)));
+        self.tree.needs_rebuild()
+    }
+}
@@ -0,0 +1,89 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use boilerplate::{Example, HandyDandyRectBuilder, ImageBuilder};
+use glutin;
+use images::ImageCache;
+use std::path::{Path, PathBuf};
+use webrender::api::*;
+
+const CHECKER: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/checker.png");
+const DOT: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/dot.png");
+
+/// The images in `res/`, at their natural size, stretched with smooth and
+/// pixelated filtering, and tiled. Editing them updates the window.
+pub struct Images {
+    cache: ImageCache,
+}
+
+impl Images {
+    pub fn new() -> Images {
+        Images {
+            cache: ImageCache::new(),
+        }
+    }
+}
+
+impl Example for Images {
+    fn render(&mut self,
+              api: &RenderApi,
+              builder: &mut DisplayListBuilder,
+              resources: &mut ResourceUpdates,
+              layout_size: LayoutSize,
+              _pipeline_id: PipelineId,
+              _document_id: DocumentId) {
+        let bounds = LayoutRect::new(LayoutPoint::zero(), layout_size);
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      bounds,
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+
+        let (checker, dot) = match (self.cache.load(api, resources, Path::new(CHECKER)),
+                                    self.cache.load(api, resources, Path::new(DOT))) {
+            (Ok(checker), Ok(dot)) => (checker, dot),
+            (Err(e), _) | (_, Err(e)) => {
+                error!("Couldn't load the example images: {}", e);
+                builder.pop_stacking_context();
+                return;
+            }
+        };
+
+        builder.push_image_at(&checker, LayoutPoint::new(20.0, 20.0));
+        builder.push_image_at(&dot, LayoutPoint::new(60.0, 20.0));
+
+        builder.push_image_stretched(&checker, (20, 80).by(200, 200));
+        builder.push_image_stretched(&checker.with_rendering(ImageRendering::Pixelated),
+                                     (240, 80).by(200, 200));
+
+        builder.push_rect((20, 300).to(440, 500), None, ColorF::new(1.0, 1.0, 1.0, 1.0));
+        builder.push_image_tiled(&dot,
+                                 (20, 300).to(440, 500),
+                                 LayoutSize::new(32.0, 32.0),
+                                 LayoutSize::new(8.0, 8.0));
+
+        builder.pop_stacking_context();
+    }
+
+    fn on_event(&mut self,
+                event: glutin::WindowEvent,
+                _api: &RenderApi,
+                _document_id: DocumentId) -> bool {
+        match event {
+            glutin::WindowEvent::Resized(..) => true,
+            _ => false,
+        }
+    }
+
+    fn watched_files(&self) -> Vec<PathBuf> {
+        vec![PathBuf::from(CHECKER), PathBuf::from(DOT)]
+    }
+
+    // The cache notices the new modification time when rendering.
+    fn on_file_changed(&mut self, path: &Path) -> bool {
+        path == Path::new(CHECKER) || path == Path::new(DOT)
+    }
+}
@@ -0,0 +1,244 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The examples the playground binary knows about by name. To add one, put it
+//! in its own module here and give it an entry in `EXAMPLES`.
+
+use boilerplate::Example;
+use gleam::gl;
+use glutin;
+use hit_test::{HitTag, HitTester};
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use webrender::api::*;
+use webrender::renderer::{ExternalImage, ExternalImageHandler, ExternalImageSource};
+
+pub mod bounce;
+pub mod external;
+pub mod grid;
+pub mod images;
+pub mod rects;
+pub mod spin;
+
+pub struct ExampleEntry {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub create: fn() -> Box<Example>,
+}
+
+fn create_grid() -> Box<Example> {
+    Box::new(grid::Grid::new())
+}
+
+fn create_bounce() -> Box<Example> {
+    Box::new(bounce::Bounce::new())
+}
+
+fn create_images() -> Box<Example> {
+    Box::new(images::Images::new())
+}
+
+fn create_external() -> Box<Example> {
+    Box::new(external::External::new())
+}
+
+fn create_spin() -> Box<Example> {
+    Box::new(spin::Spin::new())
+}
+
+fn create_rects() -> Box<Example> {
+    Box::new(rects::Rects::new())
+}
+
+pub static EXAMPLES: &'static [ExampleEntry] = &[
+    ExampleEntry {
+        name: "grid",
+        description: "a grid of rects with nested scroll frames",
+        create: create_grid,
+    },
+    ExampleEntry {
+        name: "bounce",
+        description: "a box bouncing around the window, rebuilt every frame",
+        create: create_bounce,
+    },
+    ExampleEntry {
+        name: "spin",
+        description: "spinning and fading layers animated with property bindings",
+        create: create_spin,
+    },
+    ExampleEntry {
+        name: "images",
+        description: "PNG images drawn at their size, stretched and tiled",
+        create: create_images,
+    },
+    ExampleEntry {
+        name: "external",
+        description: "our own GL drawing composited as an external image",
+        create: create_external,
+    },
+    ExampleEntry {
+        name: "rects",
+        description: "opaque and translucent rects without text, used by the reftests",
+        create: create_rects,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static ExampleEntry> {
+    EXAMPLES.iter().find(|entry| entry.name == name)
+}
+
+pub fn print_list() {
+    println!("Available examples:");
+    for entry in EXAMPLES {
+        println!("  {:<16} {}", entry.name, entry.description);
+    }
+}
+
+/// Hosts several examples in one window and switches to the next one when
+/// Tab is pressed, keeping the renderer around.
+pub struct ExampleCycler {
+    examples: Vec<(String, Box<Example>)>,
+    // Shared with the external image handler, see `CyclerImageHandler`.
+    current: Rc<Cell<usize>>,
+}
+
+impl ExampleCycler {
+    /// `first` is shown initially, and Tab goes on to `others`.
+    pub fn new(first: (String, Box<Example>), others: Vec<(String, Box<Example>)>) -> ExampleCycler {
+        let mut examples = vec![first];
+        examples.extend(others);
+        ExampleCycler {
+            examples,
+            current: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// `first` followed by every registered example other than `first_name`.
+    pub fn with_all_examples(first_name: &str, first: Box<Example>) -> ExampleCycler {
+        let others = EXAMPLES.iter()
+            .filter(|entry| entry.name != first_name)
+            .map(|entry| (entry.name.to_string(), (entry.create)()))
+            .collect();
+        ExampleCycler::new((first_name.to_string(), first), others)
+    }
+
+    fn current(&mut self) -> &mut Example {
+        &mut *self.examples[self.current.get()].1
+    }
+}
+
+/// The renderer only takes one external image handler, so this one holds
+/// those of every example and forwards to the one of the current example.
+struct CyclerImageHandler {
+    handlers: Vec<Option<Box<ExternalImageHandler>>>,
+    current: Rc<Cell<usize>>,
+}
+
+impl ExternalImageHandler for CyclerImageHandler {
+    fn lock(&mut self, key: ExternalImageId, channel_index: u8) -> ExternalImage {
+        match self.handlers[self.current.get()] {
+            Some(ref mut handler) => handler.lock(key, channel_index),
+            // Only reachable while the previous example's last frame is
+            // still being drawn.
+            None => ExternalImage {
+                u0: 0.0,
+                v0: 0.0,
+                u1: 0.0,
+                v1: 0.0,
+                source: ExternalImageSource::Invalid,
+            },
+        }
+    }
+
+    fn unlock(&mut self, key: ExternalImageId, channel_index: u8) {
+        if let Some(ref mut handler) = self.handlers[self.current.get()] {
+            handler.unlock(key, channel_index);
+        }
+    }
+}
+
+impl Example for ExampleCycler {
+    fn render(&mut self,
+              api: &RenderApi,
+              builder: &mut DisplayListBuilder,
+              resources: &mut ResourceUpdates,
+              layout_size: LayoutSize,
+              pipeline_id: PipelineId,
+              document_id: DocumentId) {
+        self.current().render(api, builder, resources, layout_size, pipeline_id, document_id);
+    }
+
+    fn on_event(&mut self,
+                event: glutin::WindowEvent,
+                api: &RenderApi,
+                document_id: DocumentId) -> bool {
+        match event {
+            glutin::WindowEvent::KeyboardInput {
+                input: glutin::KeyboardInput {
+                    state: glutin::ElementState::Pressed,
+                    virtual_keycode: Some(glutin::VirtualKeyCode::Tab),
+                    ..
+                },
+                ..
+            } if self.examples.len() > 1 => {
+                let next = (self.current.get() + 1) % self.examples.len();
+                self.current.set(next);
+                info!("Switched to the {} example", self.examples[next].0);
+                true
+            }
+            event => self.current().on_event(event, api, document_id),
+        }
+    }
+
+    fn get_external_image_handler(&self, gl: &Rc<gl::Gl>) -> Option<Box<ExternalImageHandler>> {
+        let handlers: Vec<_> = self.examples.iter()
+            .map(|&(_, ref example)| example.get_external_image_handler(gl))
+            .collect();
+        if handlers.iter().all(Option::is_none) {
+            return None;
+        }
+        Some(Box::new(CyclerImageHandler {
+            handlers,
+            current: self.current.clone(),
+        }))
+    }
+
+    fn watched_files(&self) -> Vec<PathBuf> {
+        self.examples.iter().flat_map(|&(_, ref example)| example.watched_files()).collect()
+    }
+
+    fn on_file_changed(&mut self, path: &Path) -> bool {
+        let mut needs_render = false;
+        for (index, &mut (_, ref mut example)) in self.examples.iter_mut().enumerate() {
+            let changed = example.on_file_changed(path);
+            needs_render |= changed && index == self.current.get();
+        }
+        needs_render
+    }
+
+    fn wants_animation(&self) -> bool {
+        self.examples[self.current.get()].1.wants_animation()
+    }
+
+    fn tick(&mut self, dt: f32) -> bool {
+        self.current().tick(dt)
+    }
+
+    fn dynamic_properties(&self) -> Option<DynamicProperties> {
+        self.examples[self.current.get()].1.dynamic_properties()
+    }
+
+    fn hit_tester(&self) -> Option<&HitTester> {
+        self.examples[self.current.get()].1.hit_tester()
+    }
+
+    fn on_hover(&mut self, tag: Option<HitTag>) -> bool {
+        self.current().on_hover(tag)
+    }
+
+    fn on_click(&mut self, tag: HitTag) -> bool {
+        self.current().on_click(tag)
+    }
+}
@@ -0,0 +1,76 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use boilerplate::{Example, HandyDandyRectBuilder};
+use glutin;
+use webrender::api::*;
+
+const CELL_SIZE: i32 = 50;
+const CELL_INSET: i32 = 5;
+const PALETTE: [(u8, u8, u8); 4] = [
+    (230, 80, 60),
+    (60, 180, 90),
+    (50, 110, 220),
+    (240, 200, 40),
+];
+
+fn color(r: u8, g: u8, b: u8, a: u8) -> ColorF {
+    ColorF::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0)
+}
+
+/// Opaque rects on pixel boundaries under one translucent overlay. It draws
+/// no text, so unlike `grid` it renders the same on every machine, which is
+/// what the reftests need.
+pub struct Rects;
+
+impl Rects {
+    pub fn new() -> Rects {
+        Rects
+    }
+}
+
+impl Example for Rects {
+    fn render(&mut self,
+              _api: &RenderApi,
+              builder: &mut DisplayListBuilder,
+              _resources: &mut ResourceUpdates,
+              layout_size: LayoutSize,
+              _pipeline_id: PipelineId,
+              _document_id: DocumentId) {
+        let bounds = LayoutRect::new(LayoutPoint::zero(), layout_size);
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      bounds,
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+        builder.push_rect(bounds, None, color(30, 30, 40, 255));
+
+        let columns = layout_size.width as i32 / CELL_SIZE;
+        let rows = layout_size.height as i32 / CELL_SIZE;
+        for row in 0..rows {
+            for column in 0..columns {
+                let (r, g, b) = PALETTE[((row + column) % PALETTE.len() as i32) as usize];
+                let origin = (column * CELL_SIZE + CELL_INSET, row * CELL_SIZE + CELL_INSET);
+                builder.push_rect(origin.by(CELL_SIZE - 2 * CELL_INSET, CELL_SIZE - 2 * CELL_INSET),
+                                  None,
+                                  color(r, g, b, 255));
+            }
+        }
+
+        builder.push_rect((100, 75).by(200, 150), None, color(255, 255, 255, 128));
+        builder.pop_stacking_context();
+    }
+
+    fn on_event(&mut self,
+                event: glutin::WindowEvent,
+                _api: &RenderApi,
+                _document_id: DocumentId) -> bool {
+        match event {
+            glutin::WindowEvent::Resized(..) => true,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use animation::{Animation, Animator, Easing, LayerId, Repeat, Transform2D};
+use boilerplate::{Example, HandyDandyRectBuilder};
+use glutin;
+use webrender::api::*;
+
+/// Layers that spin, pulse and fade without the display list ever being
+/// rebuilt. Space pauses, R restarts.
+pub struct Spin {
+    animator: Animator,
+    spinner: LayerId,
+    pulser: LayerId,
+    fader: LayerId,
+}
+
+impl Spin {
+    pub fn new() -> Spin {
+        let mut animator = Animator::new();
+
+        let spinner = animator.add_layer();
+        animator.animate_transform(spinner,
+            Animation::new(2.0, Transform2D::rotate(0.0), Transform2D::rotate(360.0))
+                .repeat(Repeat::Loop));
+
+        let pulser = animator.add_layer();
+        animator.animate_transform(pulser,
+            Animation::new(1.5, Transform2D::scale(1.0), Transform2D::scale(1.5))
+                .easing(Easing::EaseInOut)
+                .repeat(Repeat::Alternate));
+
+        let fader = animator.add_layer();
+        animator.animate_transform(fader,
+            Animation::new(3.0, Transform2D::translate(0.0, 0.0), Transform2D::translate(0.0, 0.0))
+                .keyframe(0.5, Transform2D::translate(0.0, 150.0))
+                .easing(Easing::Ease)
+                .repeat(Repeat::Loop));
+        animator.animate_opacity(fader,
+            Animation::new(3.0, 1.0, 1.0)
+                .keyframe(0.5, 0.1)
+                .repeat(Repeat::Loop));
+
+        Spin {
+            animator,
+            spinner,
+            pulser,
+            fader,
+        }
+    }
+}
+
+impl Example for Spin {
+    fn render(&mut self,
+              _api: &RenderApi,
+              builder: &mut DisplayListBuilder,
+              _resources: &mut ResourceUpdates,
+              layout_size: LayoutSize,
+              _pipeline_id: PipelineId,
+              _document_id: DocumentId) {
+        let bounds = LayoutRect::new(LayoutPoint::zero(), layout_size);
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      bounds,
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+
+        let layers = [
+            (self.spinner, ColorF::new(1.0, 0.6, 0.0, 1.0)),
+            (self.pulser, ColorF::new(0.0, 0.8, 0.4, 1.0)),
+            (self.fader, ColorF::new(0.2, 0.4, 1.0, 1.0)),
+        ];
+        for (i, &(layer, color)) in layers.iter().enumerate() {
+            let x = 100 + 200 * i as i32;
+            self.animator.push_stacking_context(builder, layer, (x, 100).by(120, 120));
+            builder.push_rect((0, 0).by(120, 120), None, color);
+            builder.pop_stacking_context();
+        }
+
+        builder.pop_stacking_context();
+    }
+
+    fn on_event(&mut self,
+                event: glutin::WindowEvent,
+                _api: &RenderApi,
+                _document_id: DocumentId) -> bool {
+        match event {
+            glutin::WindowEvent::Resized(..) => true,
+            glutin::WindowEvent::KeyboardInput {
+                input: glutin::KeyboardInput {
+                    state: glutin::ElementState::Pressed,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+                ..
+            } => match key {
+                glutin::VirtualKeyCode::Space => {
+                    let paused = self.animator.is_paused();
+                    self.animator.set_paused(!paused);
+                    false
+                }
+                // Rebuilding sends the restarted values even while paused.
+                glutin::VirtualKeyCode::R => {
+                    self.animator.restart();
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn wants_animation(&self) -> bool {
+        self.animator.is_animating()
+    }
+
+    fn tick(&mut self, dt: f32) -> bool {
+        self.animator.tick(dt);
+        false
+    }
+
+    fn dynamic_properties(&self) -> Option<DynamicProperties> {
+        Some(self.animator.dynamic_properties())
+    }
+}
@@ -0,0 +1,264 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use boilerplate::{self, Example};
+use cli;
+use context::{self, Capabilities};
+use error::PlaygroundError;
+use gleam::gl;
+use glutin::{self, GlContext};
+use profiling::FrameProfiler;
+use screenshot;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Instant;
+use webrender;
+use webrender::api::*;
+
+// There's no event loop to wake up when running headless, so the notifier
+// just pokes a channel that `render_frame` blocks on.
+struct HeadlessNotifier {
+    tx: Sender<()>,
+}
+
+impl RenderNotifier for HeadlessNotifier {
+    fn new_frame_ready(&mut self) {
+        self.tx.send(()).ok();
+    }
+
+    fn new_scroll_frame_ready(&mut self, _composite_needed: bool) {
+        self.tx.send(()).ok();
+    }
+}
+
+/// A framebuffer object to render into, since a headless context isn't
+/// guaranteed to come with a usable default framebuffer.
+pub struct OffscreenTarget {
+    gl: Rc<gl::Gl>,
+    fbo: gl::GLuint,
+    renderbuffers: Vec<gl::GLuint>,
+}
+
+impl OffscreenTarget {
+    pub fn new(gl: Rc<gl::Gl>, size: DeviceUintSize) -> OffscreenTarget {
+        let (width, height) = (size.width as gl::GLsizei, size.height as gl::GLsizei);
+        let fbo = gl.gen_framebuffers(1)[0];
+        let renderbuffers = gl.gen_renderbuffers(2);
+
+        gl.bind_renderbuffer(gl::RENDERBUFFER, renderbuffers[0]);
+        gl.renderbuffer_storage(gl::RENDERBUFFER, gl::RGBA8, width, height);
+        gl.bind_renderbuffer(gl::RENDERBUFFER, renderbuffers[1]);
+        gl.renderbuffer_storage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, width, height);
+
+        gl.bind_framebuffer(gl::FRAMEBUFFER, fbo);
+        gl.framebuffer_renderbuffer(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0,
+                                    gl::RENDERBUFFER, renderbuffers[0]);
+        gl.framebuffer_renderbuffer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT,
+                                    gl::RENDERBUFFER, renderbuffers[1]);
+        gl.bind_renderbuffer(gl::RENDERBUFFER, 0);
+
+        OffscreenTarget {
+            gl,
+            fbo,
+            renderbuffers,
+        }
+    }
+
+    /// WebRender draws into whatever framebuffer is bound when the frame starts.
+    pub fn bind(&self) {
+        self.gl.bind_framebuffer(gl::FRAMEBUFFER, self.fbo);
+    }
+}
+
+impl Drop for OffscreenTarget {
+    fn drop(&mut self) {
+        self.gl.delete_renderbuffers(&self.renderbuffers);
+        self.gl.delete_framebuffers(&[self.fbo]);
+    }
+}
+
+/// Renders examples without a window, into an offscreen target of a fixed size.
+pub struct HeadlessRenderer {
+    // The renderer and GL objects must be torn down before the context goes
+    // away, see `deinit`.
+    renderer: webrender::renderer::Renderer,
+    target: OffscreenTarget,
+    gl: Rc<gl::Gl>,
+    context: glutin::HeadlessContext,
+    api: RenderApi,
+    document_id: DocumentId,
+    pipeline_id: PipelineId,
+    epoch: Epoch,
+    size: DeviceUintSize,
+    device_pixel_ratio: f32,
+    frame_ready: Receiver<()>,
+    profiler: FrameProfiler,
+}
+
+impl HeadlessRenderer {
+    pub fn new(size: DeviceUintSize,
+               device_pixel_ratio: f32,
+               cli: &cli::Options,
+               options: Option<webrender::RendererOptions>) -> Result<HeadlessRenderer, PlaygroundError> {
+        let mut failures = Vec::new();
+        let mut context = None;
+        for config in context::context_configs(cli) {
+            match config.apply_headless(glutin::HeadlessRendererBuilder::new(size.width, size.height)).build() {
+                Ok(headless) => {
+                    info!("Created a headless {} context", config);
+                    context = Some(headless);
+                    break;
+                }
+                Err(e) => {
+                    warn!("Couldn't create a headless {} context: {}", config, e);
+                    failures.push((config, e));
+                }
+            }
+        }
+        let context = context.ok_or(PlaygroundError::NoContext(failures))?;
+
+        unsafe { context.make_current()? };
+
+        let gl = context::load_gl(&context);
+        Capabilities::query(&*gl, context.get_api()).report();
+
+        let target = OffscreenTarget::new(gl.clone(), size);
+
+        let opts = cli.renderer_options(device_pixel_ratio, options);
+        let (mut renderer, sender) = webrender::renderer::Renderer::new(gl.clone(), opts)?;
+        renderer.set_debug_flags(cli.debug_flags);
+        let api = sender.create_api();
+        let document_id = api.add_document(size);
+
+        let (tx, frame_ready) = channel();
+        renderer.set_render_notifier(Box::new(HeadlessNotifier { tx }));
+
+        let pipeline_id = PipelineId(0, 0);
+        api.set_root_pipeline(document_id, pipeline_id);
+
+        Ok(HeadlessRenderer {
+            renderer,
+            target,
+            gl,
+            context,
+            api,
+            document_id,
+            pipeline_id,
+            epoch: Epoch(0),
+            size,
+            device_pixel_ratio,
+            frame_ready,
+            profiler: FrameProfiler::new(),
+        })
+    }
+
+    pub fn gl(&self) -> &Rc<gl::Gl> {
+        &self.gl
+    }
+
+    pub fn size(&self) -> DeviceUintSize {
+        self.size
+    }
+
+    /// The timings of every frame rendered so far.
+    pub fn profiler(&self) -> &FrameProfiler {
+        &self.profiler
+    }
+
+    pub fn layout_size(&self) -> LayoutSize {
+        LayoutSize::new(self.size.width as f32 / self.device_pixel_ratio,
+                        self.size.height as f32 / self.device_pixel_ratio)
+    }
+
+    /// Hands the example's external image handler (if any) to the renderer.
+    pub fn set_example(&mut self, example: &Example) {
+        if let Some(external_image_handler) = example.get_external_image_handler(&self.gl) {
+            self.renderer.set_external_image_handler(external_image_handler);
+        }
+    }
+
+    /// Runs one full render -> set_display_list -> generate_frame -> render
+    /// cycle for `example`, blocking until the frame has been drawn.
+    pub fn render_frame(&mut self, example: &mut Example) -> Result<(), PlaygroundError> {
+        let layout_size = self.layout_size();
+        let timing = boilerplate::render_example(example, &self.api, self.document_id,
+                                                 self.pipeline_id, self.epoch, layout_size);
+        self.profiler.display_list(timing);
+        self.epoch.0 += 1;
+
+        self.frame_ready.recv().map_err(|_| PlaygroundError::BackendGone)?;
+        while let Ok(()) = self.frame_ready.try_recv() {}
+
+        let mut timings = self.profiler.begin_frame();
+        let update_start = Instant::now();
+        self.renderer.update();
+        self.target.bind();
+        let render_start = Instant::now();
+        self.renderer.render(self.size);
+        let finish_start = Instant::now();
+        self.gl.finish();
+        timings.update = render_start - update_start;
+        timings.render = finish_start - render_start;
+        timings.swap = finish_start.elapsed();
+        self.profiler.end_frame(timings, &mut self.renderer);
+        Ok(())
+    }
+
+    /// Reads back the last rendered frame as top-down RGBA8 rows.
+    pub fn read_pixels(&self) -> Vec<u8> {
+        self.target.bind();
+        screenshot::read_pixels_rgba8(&*self.gl, self.size)
+    }
+
+    pub fn save_png(&self, path: &Path) {
+        self.target.bind();
+        screenshot::save_screenshot(&*self.gl, self.size, path);
+    }
+
+    pub fn deinit(self) {
+        let HeadlessRenderer { renderer, target, context, .. } = self;
+        renderer.deinit();
+        drop(target);
+        drop(context);
+    }
+}
+
+/// Seconds between headless frames, as seen by animating examples.
+pub const HEADLESS_FRAME_TIME: f32 = 1.0 / 60.0;
+
+/// The headless counterpart of `main_wrapper`: renders `cli.frames` frames
+/// of `example`, optionally saves the last one as a PNG, and returns once
+/// they're done.
+pub fn headless_wrapper(example: &mut Example,
+                        options: Option<webrender::RendererOptions>,
+                        cli: &cli::Options) -> Result<(), PlaygroundError> {
+    let size = cli.headless_size();
+    let device_pixel_ratio = cli.device_pixel_ratio.unwrap_or(1.0);
+    let mut renderer = HeadlessRenderer::new(size, device_pixel_ratio, cli, options)?;
+    renderer.set_example(example);
+
+    for frame in 0..cli.frames {
+        // Animations advance by a fixed step so the output doesn't depend on
+        // how fast the frames render. Every frame rebuilds the display list
+        // anyway, so what `tick` returns doesn't matter.
+        if frame > 0 && example.wants_animation() {
+            example.tick(HEADLESS_FRAME_TIME);
+        }
+        renderer.render_frame(example)?;
+    }
+
+    info!("Rendered {} headless frames at {}x{}", cli.frames, size.width, size.height);
+    if let Some(ref path) = cli.screenshot {
+        renderer.save_png(path);
+    }
+    if let Some(ref path) = cli.profile {
+        match renderer.profiler().write(path) {
+            Ok(()) => info!("Wrote frame timings to {}", path.display()),
+            Err(e) => error!("Couldn't write {}: {}", path.display(), e),
+        }
+    }
+    renderer.deinit();
+    Ok(())
+}
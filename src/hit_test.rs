@@ -0,0 +1,89 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Finding the display item under the pointer. WebRender can't hit test
+//! yet, so examples tag the rects of their items while building the display
+//! list and the playground looks the cursor up in those. Matching tags are
+//! passed to `Example::on_hover` and `Example::on_click`.
+//!
+//! Tagged rects are in the example's layout coordinates. They don't follow
+//! scrolling or animated transforms.
+
+use std::collections::HashMap;
+use webrender::api::*;
+
+pub type HitTag = u64;
+
+// Big enough that a typical rect only lands in a few cells.
+const CELL_SIZE: f32 = 64.0;
+
+struct TaggedRect {
+    rect: LayoutRect,
+    tag: HitTag,
+}
+
+/// The tagged rects of a display list, bucketed into a uniform grid so that
+/// a lookup only looks at those in the point's cell.
+pub struct HitTester {
+    rects: Vec<TaggedRect>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+fn cell_of(point: LayoutPoint) -> (i32, i32) {
+    ((point.x / CELL_SIZE).floor() as i32, (point.y / CELL_SIZE).floor() as i32)
+}
+
+impl HitTester {
+    pub fn new() -> HitTester {
+        HitTester {
+            rects: Vec::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Forgets every tag. Call this when starting a new display list.
+    pub fn clear(&mut self) {
+        self.rects.clear();
+        self.cells.clear();
+    }
+
+    /// Tags `rect`. Rects tagged later are on top of earlier ones, like the
+    /// items they belong to.
+    pub fn tag(&mut self, rect: LayoutRect, tag: HitTag) {
+        if rect.size.width <= 0.0 || rect.size.height <= 0.0 {
+            return;
+        }
+        let index = self.rects.len();
+        self.rects.push(TaggedRect { rect, tag });
+
+        let (x0, y0) = cell_of(rect.origin);
+        let (x1, y1) = cell_of(rect.bottom_right());
+        for y in y0..y1 + 1 {
+            for x in x0..x1 + 1 {
+                self.cells.entry((x, y)).or_insert_with(Vec::new).push(index);
+            }
+        }
+    }
+
+    /// Tags the part of `rect` inside `clip`, for items in scroll frames or
+    /// with a local clip.
+    pub fn tag_clipped(&mut self, rect: LayoutRect, clip: LayoutRect, tag: HitTag) {
+        if let Some(visible) = rect.intersection(&clip) {
+            self.tag(visible, tag);
+        }
+    }
+
+    /// The tag of the topmost rect containing `point`.
+    pub fn hit_test(&self, point: LayoutPoint) -> Option<HitTag> {
+        let indices = match self.cells.get(&cell_of(point)) {
+            Some(indices) => indices,
+            None => return None,
+        };
+        indices.iter()
+            .rev()
+            .map(|&index| &self.rects[index])
+            .find(|tagged| tagged.rect.contains(&point))
+            .map(|tagged| tagged.tag)
+    }
+}
@@ -0,0 +1,166 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! PNG and JPEG files as WebRender images. Use them through
+//! `boilerplate::ImageBuilder`.
+
+use image;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use webrender::api::*;
+
+/// Images larger than this in either dimension are split into tiles by
+/// WebRender, they might not fit in the texture cache otherwise.
+const MAX_UNTILED_SIZE: u32 = 2048;
+const TILE_SIZE: TileSize = 512;
+
+#[derive(Debug)]
+pub enum ImageLoadError {
+    Io(io::Error),
+    Decode(image::ImageError),
+}
+
+impl fmt::Display for ImageLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ImageLoadError::Io(ref e) => write!(f, "couldn't read image: {}", e),
+            ImageLoadError::Decode(ref e) => write!(f, "couldn't decode image: {}", e),
+        }
+    }
+}
+
+impl error::Error for ImageLoadError {
+    fn description(&self) -> &str {
+        match *self {
+            ImageLoadError::Io(..) => "couldn't read image",
+            ImageLoadError::Decode(..) => "couldn't decode image",
+        }
+    }
+}
+
+impl From<io::Error> for ImageLoadError {
+    fn from(e: io::Error) -> ImageLoadError {
+        ImageLoadError::Io(e)
+    }
+}
+
+impl From<image::ImageError> for ImageLoadError {
+    fn from(e: image::ImageError) -> ImageLoadError {
+        ImageLoadError::Decode(e)
+    }
+}
+
+/// Decodes the file at `path` into the premultiplied BGRA that WebRender
+/// wants.
+pub fn decode(path: &Path) -> Result<(ImageDescriptor, ImageData), ImageLoadError> {
+    let rgba = image::open(path)?.to_rgba();
+    let (width, height) = rgba.dimensions();
+
+    let mut is_opaque = true;
+    let mut pixels = rgba.into_raw();
+    for pixel in pixels.chunks_mut(4) {
+        let alpha = pixel[3] as u32;
+        is_opaque &= alpha == 255;
+        let premultiply = |channel: u8| (channel as u32 * alpha / 255) as u8;
+        let (r, g, b) = (premultiply(pixel[0]), premultiply(pixel[1]), premultiply(pixel[2]));
+        pixel[0] = b;
+        pixel[1] = g;
+        pixel[2] = r;
+    }
+
+    let descriptor = ImageDescriptor::new(width, height, ImageFormat::BGRA8, is_opaque);
+    Ok((descriptor, ImageData::new(pixels)))
+}
+
+/// An image that has been added to WebRender.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoadedImage {
+    pub key: ImageKey,
+    pub size: LayoutSize,
+    /// How the `ImageBuilder` methods filter the image when scaling it.
+    pub rendering: ImageRendering,
+}
+
+impl LoadedImage {
+    pub fn with_rendering(self, rendering: ImageRendering) -> LoadedImage {
+        LoadedImage { rendering, .. self }
+    }
+}
+
+struct CachedImage {
+    key: ImageKey,
+    descriptor: ImageDescriptor,
+    modified: Option<SystemTime>,
+}
+
+/// Keeps the image key of every file loaded through it, so re-rendering
+/// doesn't add the same image again. Files that changed on disk since they
+/// were last loaded are decoded again and sent with `update_image`.
+pub struct ImageCache {
+    images: HashMap<PathBuf, CachedImage>,
+}
+
+impl ImageCache {
+    pub fn new() -> ImageCache {
+        ImageCache {
+            images: HashMap::new(),
+        }
+    }
+
+    pub fn load(&mut self,
+                api: &RenderApi,
+                resources: &mut ResourceUpdates,
+                path: &Path) -> Result<LoadedImage, ImageLoadError> {
+        let modified = fs::metadata(path)?.modified().ok();
+
+        // Keys belong to the `RenderApi` that made them, and the shader
+        // reload replaces that.
+        let cached = self.images.get(path)
+            .map(|image| (image.key, image.descriptor, image.key.0 == api.get_namespace_id(),
+                          modified.is_some() && image.modified == modified));
+        let (key, descriptor) = match cached {
+            Some((key, descriptor, true, true)) => (key, descriptor),
+            Some((key, _, true, false)) => {
+                let (descriptor, data) = decode(path)?;
+                resources.update_image(key, descriptor, data, None);
+                (key, descriptor)
+            }
+            _ => {
+                let (descriptor, data) = decode(path)?;
+                let key = api.generate_image_key();
+                let tiling = if descriptor.width > MAX_UNTILED_SIZE || descriptor.height > MAX_UNTILED_SIZE {
+                    Some(TILE_SIZE)
+                } else {
+                    None
+                };
+                resources.add_image(key, descriptor, data, tiling);
+                (key, descriptor)
+            }
+        };
+        self.images.insert(path.to_owned(), CachedImage { key, descriptor, modified });
+
+        Ok(LoadedImage {
+            key,
+            size: LayoutSize::new(descriptor.width as f32, descriptor.height as f32),
+            rendering: ImageRendering::Auto,
+        })
+    }
+
+    /// Deletes the image for `path`, if it was loaded.
+    pub fn remove(&mut self, resources: &mut ResourceUpdates, path: &Path) {
+        if let Some(image) = self.images.remove(path) {
+            resources.delete_image(image.key);
+        }
+    }
+
+    /// Every file loaded so far, for `Example::watched_files`.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.images.keys().cloned().collect()
+    }
+}
@@ -0,0 +1,41 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+extern crate app_units;
+extern crate bincode;
+extern crate clap;
+extern crate env_logger;
+extern crate gleam;
+extern crate glutin;
+extern crate image;
+#[macro_use]
+extern crate log;
+extern crate rusttype;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate serde_yaml;
+extern crate webrender;
+
+pub mod animation;
+pub mod bench;
+pub mod boilerplate;
+pub mod capture;
+pub mod cli;
+pub mod context;
+pub mod error;
+pub mod examples;
+pub mod headless;
+pub mod hit_test;
+pub mod images;
+pub mod logging;
+pub mod profiling;
+pub mod reftest;
+pub mod scene;
+pub mod screenshot;
+pub mod support;
+pub mod text;
+mod watcher;
+pub mod widgets;
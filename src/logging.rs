@@ -0,0 +1,115 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Sets up the `log` facade. Messages go to stderr, filtered like
+//! env_logger's `RUST_LOG` (e.g. `webrender_playground::boilerplate=trace`),
+//! and optionally also to a file as one JSON object per line.
+
+use env_logger::{LogBuilder, Logger};
+use log::{self, Log, LogMetadata, LogRecord, SetLoggerError};
+use serde_json;
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Used when neither `--log` nor `RUST_LOG` say otherwise. Per-event and
+/// per-frame messages are at debug and trace level.
+pub const DEFAULT_FILTER: &'static str = "warn,webrender_playground=info,replay=info";
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    /// Seconds since the Unix epoch.
+    time: f64,
+    level: String,
+    target: &'a str,
+    file: &'a str,
+    line: u32,
+    message: String,
+}
+
+struct PlaygroundLogger {
+    stderr: Logger,
+    json: Option<Mutex<BufWriter<File>>>,
+}
+
+impl Log for PlaygroundLogger {
+    fn enabled(&self, metadata: &LogMetadata) -> bool {
+        self.stderr.enabled(metadata)
+    }
+
+    fn log(&self, record: &LogRecord) {
+        if !self.stderr.matches(record) {
+            return;
+        }
+        self.stderr.log(record);
+
+        if let Some(ref json) = self.json {
+            let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            let line = JsonRecord {
+                time: time.as_secs() as f64 + time.subsec_nanos() as f64 * 1e-9,
+                level: record.level().to_string(),
+                target: record.target(),
+                file: record.location().file(),
+                line: record.location().line(),
+                message: record.args().to_string(),
+            };
+            // Logging has nowhere to report its own failures.
+            let mut writer = json.lock().unwrap();
+            if serde_json::to_writer(&mut *writer, &line).is_ok() {
+                writer.write_all(b"\n").ok();
+                writer.flush().ok();
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LoggingError {
+    Io(io::Error),
+    AlreadySet(SetLoggerError),
+}
+
+impl fmt::Display for LoggingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoggingError::Io(ref e) => write!(f, "couldn't create the JSON log: {}", e),
+            LoggingError::AlreadySet(..) => write!(f, "a logger was already installed"),
+        }
+    }
+}
+
+impl error::Error for LoggingError {
+    fn description(&self) -> &str {
+        match *self {
+            LoggingError::Io(..) => "couldn't create the JSON log",
+            LoggingError::AlreadySet(..) => "a logger was already installed",
+        }
+    }
+}
+
+/// Installs the logger. `filter` takes precedence over `RUST_LOG`, which
+/// takes precedence over `DEFAULT_FILTER`.
+pub fn init(filter: Option<&str>, json_path: Option<&Path>) -> Result<(), LoggingError> {
+    let env_filter = ::std::env::var("RUST_LOG").ok();
+    let filter = filter.or(env_filter.as_ref().map(|filter| &filter[..])).unwrap_or(DEFAULT_FILTER);
+
+    let mut builder = LogBuilder::new();
+    builder.format(|record| format!("{}: {}: {}", record.level(), record.target(), record.args()));
+    builder.parse(filter);
+    let stderr = builder.build();
+
+    let json = match json_path {
+        Some(path) => Some(Mutex::new(BufWriter::new(File::create(path).map_err(LoggingError::Io)?))),
+        None => None,
+    };
+
+    log::set_logger(|max_level| {
+        max_level.set(stderr.filter());
+        Box::new(PlaygroundLogger { stderr, json })
+    }).map_err(LoggingError::AlreadySet)
+}
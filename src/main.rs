@@ -2,175 +2,133 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-extern crate gleam;
-extern crate glutin;
+#[macro_use]
+extern crate log;
 extern crate webrender;
+extern crate webrender_playground;
 
-mod boilerplate;
-// mod support;
-
-use boilerplate::{Example, HandyDandyRectBuilder};
+use std::process;
 use webrender::api::*;
-
-struct App {
-    cursor_position: WorldPoint,
+use webrender_playground::{bench, boilerplate, examples, headless, logging, reftest};
+use webrender_playground::bench::BenchOptions;
+use webrender_playground::boilerplate::{App, Example, HandyDandyRectBuilder, Nested, Pane};
+use webrender_playground::cli::Options;
+use webrender_playground::error::PlaygroundError;
+use webrender_playground::examples::ExampleCycler;
+use webrender_playground::reftest::{RefTest, RefTestOptions};
+use webrender_playground::scene::SceneExample;
+
+fn create_rects() -> Box<Example> {
+    Box::new(examples::rects::Rects::new())
 }
 
-impl Example for App {
-    fn render(&mut self,
-              _api: &RenderApi,
-              builder: &mut DisplayListBuilder,
-              _resources: &mut ResourceUpdates,
-              layout_size: LayoutSize,
-              _pipeline_id: PipelineId,
-              _document_id: DocumentId) {
-        println!("rendering at size {:?}", layout_size);
-        let bounds = LayoutRect::new(LayoutPoint::zero(), layout_size);
-        builder.push_stacking_context(ScrollPolicy::Scrollable,
-                                      bounds,
-                                      None,
-                                      TransformStyle::Flat,
-                                      None,
-                                      MixBlendMode::Normal,
-                                      Vec::new());
-
-        let grid_rows: usize = 10;
-        let grid_cols: usize = 10;
-        let padding = 10.0;
+// Examples that draw text depend on the fonts installed, so only text-free
+// ones belong here.
+fn reftests() -> Vec<RefTest> {
+    vec![
+        RefTest {
+            name: "rects",
+            size: DeviceUintSize::new(400, 300),
+            device_pixel_ratio: 1.0,
+            make_example: create_rects,
+        },
+        RefTest {
+            name: "rects",
+            size: DeviceUintSize::new(800, 600),
+            device_pixel_ratio: 2.0,
+            make_example: create_rects,
+        },
+    ]
+}
 
-        let cell_size = LayoutSize::new(
-            (layout_size.width-padding) / (grid_cols as f32),
-            (layout_size.height-padding) / (grid_rows as f32));
-        let rect_size = cell_size - LayoutSize::new(padding, padding);
+// Where `--iframe` puts its example, in the main example's coordinates.
+const IFRAME_RECT: (i32, i32, i32, i32) = (100, 100, 400, 300);
 
-        for r in 0..grid_rows {
-            for c in 0..grid_cols {
-                let pt = LayoutPoint::new(
-                    padding+(c as f32)*cell_size.width,
-                    padding+(r as f32)*cell_size.height);
-                let rect = LayoutRect::new(pt, rect_size);
-                builder.push_rect(rect, None, ColorF::new(1.0, 1.0, 1.0, 1.0));
-            }
+fn create_example(name: &str) -> (&'static str, Box<Example>) {
+    match examples::find(name) {
+        Some(entry) => (entry.name, (entry.create)()),
+        None => {
+            error!("Unknown example {:?}", name);
+            examples::print_list();
+            process::exit(1);
         }
+    }
+}
 
+fn run_app<'a>(cli: &'a Options,
+               panes: Vec<Pane<'a>>,
+               second: Option<(&str, &'a mut Example)>) -> Result<(), PlaygroundError> {
+    let mut app = App::new(cli);
+    app.open_window("WebRender Playground", panes, None)?;
+    if let Some((name, example)) = second {
+        let title = format!("WebRender Playground - {}", name);
+        app.open_window(&title, vec![Pane::full_window(example)], None)?;
+    }
+    app.run()
+}
 
-        if false {   // scrolling and clips stuff
-            // let's make a scrollbox
-            let scrollbox = (0, 0).to(300, 400);
-            builder.push_stacking_context(ScrollPolicy::Scrollable,
-                                          LayoutRect::new(LayoutPoint::new(10.0, 10.0),
-                                                          LayoutSize::zero()),
-                                          None,
-                                          TransformStyle::Flat,
-                                          None,
-                                          MixBlendMode::Normal,
-                                          Vec::new());
-            // set the scrolling clip
-            let clip_id = builder.define_scroll_frame(None,
-                                                      (0, 0).by(1000, 1000),
-                                                      scrollbox,
-                                                      vec![],
-                                                      None,
-                                                      ScrollSensitivity::ScriptAndInputEvents);
-            builder.push_clip_id(clip_id);
-
-            // now put some content into it.
-            // start with a white background
-            builder.push_rect((0, 0).to(1000, 1000), None, ColorF::new(1.0, 1.0, 1.0, 1.0));
-
-            // let's make a 50x50 blue square as a visual reference
-            builder.push_rect((0, 0).to(50, 50), None, ColorF::new(0.0, 0.0, 1.0, 1.0));
-
-            // and a 50x50 green square next to it with an offset clip
-            // to see what that looks like
-            builder.push_rect((50, 0).to(100, 50),
-                              Some(LocalClip::from((60, 10).to(110, 60))),
-                              ColorF::new(0.0, 1.0, 0.0, 1.0));
-
-            // Below the above rectangles, set up a nested scrollbox. It's still in
-            // the same stacking context, so note that the rects passed in need to
-            // be relative to the stacking context.
-            let nested_clip_id = builder.define_scroll_frame(None,
-                                                             (0, 100).to(300, 400),
-                                                             (0, 100).to(200, 300),
-                                                             vec![],
-                                                             None,
-                                                             ScrollSensitivity::ScriptAndInputEvents);
-            builder.push_clip_id(nested_clip_id);
-
-            // give it a giant gray background just to distinguish it and to easily
-            // visually identify the nested scrollbox
-            builder.push_rect((-1000, -1000).to(5000, 5000), None, ColorF::new(0.5, 0.5, 0.5, 1.0));
-
-            // add a teal square to visualize the scrolling/clipping behaviour
-            // as you scroll the nested scrollbox with WASD keys
-            builder.push_rect((0, 100).to(50, 150), None, ColorF::new(0.0, 1.0, 1.0, 1.0));
-
-            // just for good measure add another teal square in the bottom-right
-            // corner of the nested scrollframe content, which can be scrolled into
-            // view by the user
-            builder.push_rect((250, 350).to(300, 400), None, ColorF::new(0.0, 1.0, 1.0, 1.0));
-
-            builder.pop_clip_id(); // nested_clip_id
+fn main() {
+    let cli = Options::from_args();
+    if let Err(e) = logging::init(cli.log_filter.as_ref().map(|filter| &filter[..]),
+                                  cli.log_json.as_ref().map(|path| &**path)) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 
-            builder.pop_clip_id(); // clip_id
-            builder.pop_stacking_context();
-        }
+    if cli.reftest {
+        let passed = reftest::run_reftests(&reftests(), &RefTestOptions::from_cli(&cli), &cli);
+        process::exit(if passed { 0 } else { 1 });
+    }
 
-        builder.pop_stacking_context();
+    if cli.bench {
+        let passed = bench::run_benchmarks(&BenchOptions::from_cli(&cli), &cli);
+        process::exit(if passed { 0 } else { 1 });
     }
 
-    fn on_event(&mut self,
-                event: glutin::WindowEvent,
-                api: &RenderApi,
-                document_id: DocumentId) -> bool {
-        match event {
-            glutin::WindowEvent::Resized(w, h) => return true,
-            /*
-            glutin::WindowEvent::KeyboardInput(glutin::ElementState::Pressed, _, Some(key)) => {
-                let offset = match key {
-                     glutin::VirtualKeyCode::Down => (0.0, -10.0),
-                     glutin::VirtualKeyCode::Up => (0.0, 10.0),
-                     glutin::VirtualKeyCode::Right => (-10.0, 0.0),
-                     glutin::VirtualKeyCode::Left => (10.0, 0.0),
-                     _ => return false,
-                };
+    if cli.list {
+        examples::print_list();
+        return;
+    }
 
-                api.scroll(document_id,
-                           ScrollLocation::Delta(LayoutVector2D::new(offset.0, offset.1)),
-                           self.cursor_position,
-                           ScrollEventPhase::Start);
-            }
-            glutin::WindowEvent::MouseMoved(x, y) => {
-                self.cursor_position = WorldPoint::new(x as f32, y as f32);
+    let (name, mut example): (&str, Box<Example>) = match cli.scene {
+        Some(ref path) => match SceneExample::new(path.clone()) {
+            Ok(scene) => ("scene", Box::new(scene)),
+            Err(e) => {
+                error!("Couldn't load {}: {}", path.display(), e);
+                process::exit(1);
             }
-            glutin::WindowEvent::MouseWheel(delta, _, event_cursor_position) => {
-                if let Some((x, y)) = event_cursor_position {
-                    self.cursor_position = WorldPoint::new(x as f32, y as f32);
-                }
-
-                const LINE_HEIGHT: f32 = 38.0;
-                let (dx, dy) = match delta {
-                    glutin::MouseScrollDelta::LineDelta(dx, dy) => (dx, dy * LINE_HEIGHT),
-                    glutin::MouseScrollDelta::PixelDelta(dx, dy) => (dx, dy),
-                };
+        },
+        None => create_example(&cli.example),
+    };
 
-                api.scroll(document_id,
-                           ScrollLocation::Delta(LayoutVector2D::new(dx, dy)),
-                           self.cursor_position,
-                           ScrollEventPhase::Start);
-            }
-            */
-            _ => ()
+    if cli.headless {
+        if let Err(e) = headless::headless_wrapper(&mut *example, None, &cli) {
+            error!("{}", e);
+            process::exit(1);
+        }
+    } else {
+        let mut cycler = ExampleCycler::with_all_examples(name, example);
+        let mut split = cli.split.as_ref().map(|name| create_example(name).1);
+        let mut iframe = cli.iframe.as_ref().map(|name| create_example(name).1);
+        let mut second = cli.second_window.as_ref().map(|name| create_example(name));
+
+        let mut content = Nested::new(&mut cycler);
+        if let Some(ref mut iframe) = iframe {
+            let (x, y, w, h) = IFRAME_RECT;
+            content = content.with_iframe((x, y).by(w, h), Nested::new(&mut **iframe));
+        }
+        let panes = match split {
+            Some(ref mut split) => vec![
+                Pane::new([0.0, 0.0, 0.5, 1.0], content),
+                Pane::new([0.5, 0.0, 0.5, 1.0], Nested::new(&mut **split)),
+            ],
+            None => vec![Pane::new(boilerplate::FULL_WINDOW, content)],
+        };
+
+        let second = second.as_mut().map(|&mut (name, ref mut example)| (name, &mut **example));
+        if let Err(e) = run_app(&cli, panes, second) {
+            error!("{}", e);
+            process::exit(1);
         }
-
-        false
     }
 }
-
-fn main() {
-    let mut app = App {
-        cursor_position: WorldPoint::zero(),
-    };
-    boilerplate::main_wrapper(&mut app, None);
-}
@@ -0,0 +1,265 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Per-frame timings of the boilerplate loop, combined with WebRender's own
+//! CPU and GPU profiles, and written out as CSV or as Chrome trace events
+//! (for `chrome://tracing`) with `--profile-out PATH`.
+
+use serde_json;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use webrender::renderer::{CpuProfile, Renderer};
+
+/// One display list built and sent to WebRender.
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayListTiming {
+    pub start: Instant,
+    /// `Example::render` plus the iframes and the help overlay, up to
+    /// `finalize`.
+    pub build: Duration,
+    /// `set_display_list`, which hands the serialized list to the backend.
+    pub send: Duration,
+    /// The size of the serialized display list.
+    pub bytes: usize,
+}
+
+/// Where the time went for one presented frame.
+#[derive(Clone, Debug)]
+pub struct FrameTimings {
+    pub start: Instant,
+    /// Every display list sent since the previous frame.
+    pub display_lists: Vec<DisplayListTiming>,
+    pub update: Duration,
+    pub render: Duration,
+    /// `swap_buffers`, or waiting for the GPU to finish when headless.
+    pub swap: Duration,
+    /// WebRender's CPU profile, when `render` had a frame to draw.
+    pub backend_ns: Option<u64>,
+    pub composite_ns: Option<u64>,
+    pub draw_calls: Option<usize>,
+    /// From GPU timer queries, which come back a few frames later. Frames
+    /// still waiting on them when profiling stops have none.
+    pub gpu_ns: Option<u64>,
+}
+
+impl FrameTimings {
+    pub fn build(&self) -> Duration {
+        self.display_lists.iter().fold(Duration::new(0, 0), |total, list| total + list.build)
+    }
+
+    pub fn send(&self) -> Duration {
+        self.display_lists.iter().fold(Duration::new(0, 0), |total, list| total + list.send)
+    }
+
+    pub fn display_list_bytes(&self) -> usize {
+        self.display_lists.iter().map(|list| list.bytes).sum()
+    }
+
+    /// From the first display list being built to the end of the swap.
+    pub fn total(&self) -> Duration {
+        let start = self.display_lists.first().map_or(self.start, |list| list.start);
+        (self.start - start) + self.update + self.render + self.swap
+    }
+}
+
+pub fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1e3 + duration.subsec_nanos() as f64 * 1e-6
+}
+
+fn as_us(duration: Duration) -> f64 {
+    as_ms(duration) * 1e3
+}
+
+/// Collects `FrameTimings` as frames are drawn.
+pub struct FrameProfiler {
+    start: Instant,
+    pending: Vec<DisplayListTiming>,
+    frames: Vec<FrameTimings>,
+    // WebRender's CPU profiles of frames still waiting on their GPU time,
+    // along with the index of the frame they belong to.
+    awaiting_gpu: Vec<(CpuProfile, usize)>,
+}
+
+impl FrameProfiler {
+    pub fn new() -> FrameProfiler {
+        FrameProfiler {
+            start: Instant::now(),
+            pending: Vec::new(),
+            frames: Vec::new(),
+            awaiting_gpu: Vec::new(),
+        }
+    }
+
+    pub fn frames(&self) -> &[FrameTimings] {
+        &self.frames
+    }
+
+    /// Counts the display list towards the next frame.
+    pub fn display_list(&mut self, timing: DisplayListTiming) {
+        self.pending.push(timing);
+    }
+
+    /// Starts timing a frame, with the display lists sent since the last one.
+    pub fn begin_frame(&mut self) -> FrameTimings {
+        FrameTimings {
+            start: Instant::now(),
+            display_lists: self.pending.drain(..).collect(),
+            update: Duration::new(0, 0),
+            render: Duration::new(0, 0),
+            swap: Duration::new(0, 0),
+            backend_ns: None,
+            composite_ns: None,
+            draw_calls: None,
+            gpu_ns: None,
+        }
+    }
+
+    /// Stores `frame`, along with whatever profiles `renderer` has
+    /// gathered since the last call.
+    pub fn end_frame(&mut self, mut frame: FrameTimings, renderer: &mut Renderer) {
+        let index = self.frames.len();
+        let (cpu_profiles, gpu_profiles) = renderer.get_frame_profiles();
+        // `render` draws at most one frame, so there's at most one new CPU
+        // profile.
+        if let Some(cpu) = cpu_profiles.into_iter().last() {
+            frame.backend_ns = Some(cpu.backend_time_ns);
+            frame.composite_ns = Some(cpu.composite_time_ns);
+            frame.draw_calls = Some(cpu.draw_calls);
+            self.awaiting_gpu.push((cpu, index));
+        }
+        self.frames.push(frame);
+
+        for gpu in gpu_profiles {
+            if let Some(position) = self.awaiting_gpu.iter().position(|&(ref cpu, _)| cpu.frame_id == gpu.frame_id) {
+                let (_, frame_index) = self.awaiting_gpu.remove(position);
+                self.frames[frame_index].gpu_ns = Some(gpu.paint_time_ns);
+            }
+        }
+    }
+
+    /// Writes Chrome trace events if `path` ends in `.json`, CSV otherwise.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => self.write_trace(&mut writer)?,
+            _ => self.write_csv(&mut writer)?,
+        }
+        writer.flush()
+    }
+
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "frame,start_ms,build_ms,send_ms,display_list_bytes,update_ms,render_ms,\
+                          swap_ms,backend_ms,composite_ms,draw_calls,gpu_ms")?;
+        for (index, frame) in self.frames.iter().enumerate() {
+            write!(writer, "{},{:.3},{:.3},{:.3},{},{:.3},{:.3},{:.3}",
+                   index,
+                   as_ms(frame.start - self.start),
+                   as_ms(frame.build()),
+                   as_ms(frame.send()),
+                   frame.display_list_bytes(),
+                   as_ms(frame.update),
+                   as_ms(frame.render),
+                   as_ms(frame.swap))?;
+            // Missing profiles are left empty.
+            for value in &[frame.backend_ns, frame.composite_ns] {
+                match *value {
+                    Some(ns) => write!(writer, ",{:.3}", ns as f64 * 1e-6)?,
+                    None => write!(writer, ",")?,
+                }
+            }
+            match frame.draw_calls {
+                Some(draw_calls) => write!(writer, ",{}", draw_calls)?,
+                None => write!(writer, ",")?,
+            }
+            match frame.gpu_ns {
+                Some(ns) => writeln!(writer, ",{:.3}", ns as f64 * 1e-6)?,
+                None => writeln!(writer, ",")?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Our own phases are complete events on the main thread's track.
+    /// WebRender only reports how long its backend, compositing and the GPU
+    /// took, not when, so those are counters at the start of each frame.
+    pub fn write_trace<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut events = Vec::new();
+        for (index, frame) in self.frames.iter().enumerate() {
+            for list in &frame.display_lists {
+                events.push(TraceEvent::span("build display list", list.start - self.start, list.build));
+                events.push(TraceEvent::span("set_display_list", list.start + list.build - self.start, list.send));
+            }
+            let update_start = frame.start - self.start;
+            let render_start = update_start + frame.update;
+            let swap_start = render_start + frame.render;
+            events.push(TraceEvent::span("update", update_start, frame.update));
+            events.push(TraceEvent::span("render", render_start, frame.render));
+            events.push(TraceEvent::span("swap", swap_start, frame.swap));
+
+            let mut args = BTreeMap::new();
+            args.insert("frame", index as f64);
+            args.insert("display_list_bytes", frame.display_list_bytes() as f64);
+            if let Some(ns) = frame.backend_ns {
+                args.insert("backend_ms", ns as f64 * 1e-6);
+            }
+            if let Some(ns) = frame.composite_ns {
+                args.insert("composite_ms", ns as f64 * 1e-6);
+            }
+            if let Some(draw_calls) = frame.draw_calls {
+                args.insert("draw_calls", draw_calls as f64);
+            }
+            if let Some(ns) = frame.gpu_ns {
+                args.insert("gpu_ms", ns as f64 * 1e-6);
+            }
+            events.push(TraceEvent {
+                name: "webrender",
+                ph: "C",
+                ts: as_us(update_start),
+                dur: None,
+                pid: 1,
+                tid: 1,
+                args: Some(args),
+            });
+        }
+        serde_json::to_writer(writer, &Trace { traceEvents: events })
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize)]
+struct Trace {
+    traceEvents: Vec<TraceEvent>,
+}
+
+#[derive(Serialize)]
+struct TraceEvent {
+    name: &'static str,
+    ph: &'static str,
+    /// In microseconds.
+    ts: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<f64>,
+    pid: u32,
+    tid: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    args: Option<BTreeMap<&'static str, f64>>,
+}
+
+impl TraceEvent {
+    fn span(name: &'static str, start: Duration, duration: Duration) -> TraceEvent {
+        TraceEvent {
+            name,
+            ph: "X",
+            ts: as_us(start),
+            dur: Some(as_us(duration)),
+            pid: 1,
+            tid: 1,
+            args: None,
+        }
+    }
+}
@@ -0,0 +1,212 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use boilerplate::Example;
+use cli;
+use headless::HeadlessRenderer;
+use image;
+use screenshot;
+use std::fs;
+use std::path::{Path, PathBuf};
+use webrender::api::DeviceUintSize;
+
+/// One example rendered at one size and device pixel ratio, checked against
+/// a reference PNG.
+pub struct RefTest {
+    pub name: &'static str,
+    /// The size of the output in device pixels.
+    pub size: DeviceUintSize,
+    pub device_pixel_ratio: f32,
+    pub make_example: fn() -> Box<Example>,
+}
+
+impl RefTest {
+    fn file_name(&self, suffix: &str) -> String {
+        format!("{}-{}x{}@{}x{}.png",
+                self.name, self.size.width, self.size.height, self.device_pixel_ratio, suffix)
+    }
+}
+
+pub struct RefTestOptions {
+    /// Where the reference images live.
+    pub dir: PathBuf,
+    /// Where actual and diff images of failing tests get written.
+    pub output_dir: PathBuf,
+    /// The largest per-channel difference that still counts as a match.
+    pub tolerance: u8,
+    /// How many pixels may differ by more than `tolerance` before failing.
+    pub max_differing_pixels: usize,
+    /// Overwrite the references with the current output instead of comparing.
+    pub bless: bool,
+}
+
+impl Default for RefTestOptions {
+    fn default() -> RefTestOptions {
+        RefTestOptions {
+            dir: PathBuf::from("reftests"),
+            output_dir: PathBuf::from("reftest-output"),
+            tolerance: 2,
+            max_differing_pixels: 0,
+            bless: false,
+        }
+    }
+}
+
+impl RefTestOptions {
+    pub fn from_cli(cli: &cli::Options) -> RefTestOptions {
+        RefTestOptions {
+            output_dir: cli.output_dir.join("reftest-output"),
+            tolerance: cli.tolerance,
+            bless: cli.bless,
+            .. RefTestOptions::default()
+        }
+    }
+}
+
+enum Outcome {
+    Pass,
+    Blessed,
+    MissingReference,
+    SizeMismatch,
+    Fail { differing_pixels: usize, max_difference: u8 },
+    /// The test couldn't be run.
+    Error(String),
+}
+
+struct Comparison {
+    differing_pixels: usize,
+    max_difference: u8,
+    diff_image: Vec<u8>,
+}
+
+// Pixels that differ are painted solid red, matching ones are kept as a
+// faded copy of the actual output so the failure is easy to locate.
+fn compare(actual: &[u8], reference: &[u8], tolerance: u8) -> Comparison {
+    let mut differing_pixels = 0;
+    let mut max_difference = 0;
+    let mut diff_image = Vec::with_capacity(actual.len());
+
+    for (a, r) in actual.chunks(4).zip(reference.chunks(4)) {
+        let difference = a.iter().zip(r.iter())
+            .map(|(&a, &r)| if a > r { a - r } else { r - a })
+            .max()
+            .unwrap_or(0);
+        if difference > max_difference {
+            max_difference = difference;
+        }
+
+        if difference > tolerance {
+            differing_pixels += 1;
+            diff_image.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            diff_image.extend_from_slice(&[a[0] / 4, a[1] / 4, a[2] / 4, 255]);
+        }
+    }
+
+    Comparison {
+        differing_pixels,
+        max_difference,
+        diff_image,
+    }
+}
+
+fn load_reference(path: &Path) -> Result<Option<image::RgbaImage>, image::ImageError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(image::open(path)?.to_rgba()))
+}
+
+// Saves `pixels` as a PNG, creating its directory if needed.
+fn save(path: &Path, size: DeviceUintSize, pixels: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("couldn't create {}: {}", dir.display(), e))?;
+    }
+    screenshot::save_png(path, size, pixels)
+        .map_err(|e| format!("couldn't write {}: {}", path.display(), e))
+}
+
+fn run_reftest(test: &RefTest, options: &RefTestOptions, cli: &cli::Options) -> Outcome {
+    let mut example = (test.make_example)();
+    let mut renderer = match HeadlessRenderer::new(test.size, test.device_pixel_ratio, cli, None) {
+        Ok(renderer) => renderer,
+        Err(e) => return Outcome::Error(e.to_string()),
+    };
+    renderer.set_example(&*example);
+    if let Err(e) = renderer.render_frame(&mut *example) {
+        return Outcome::Error(e.to_string());
+    }
+    let actual = renderer.read_pixels();
+    renderer.deinit();
+
+    let reference_path = options.dir.join(test.file_name(""));
+    if options.bless {
+        return match save(&reference_path, test.size, &actual) {
+            Ok(()) => Outcome::Blessed,
+            Err(e) => Outcome::Error(e),
+        };
+    }
+
+    let reference = match load_reference(&reference_path) {
+        Ok(Some(reference)) => reference,
+        Ok(None) => return Outcome::MissingReference,
+        Err(e) => return Outcome::Error(format!("couldn't load {}: {}", reference_path.display(), e)),
+    };
+    if reference.dimensions() != (test.size.width, test.size.height) {
+        return Outcome::SizeMismatch;
+    }
+    let reference = reference.into_raw();
+
+    let comparison = compare(&actual, &reference, options.tolerance);
+    if comparison.differing_pixels <= options.max_differing_pixels {
+        return Outcome::Pass;
+    }
+
+    let actual_path = options.output_dir.join(test.file_name("-actual"));
+    let diff_path = options.output_dir.join(test.file_name("-diff"));
+    let saved = save(&actual_path, test.size, &actual)
+        .and_then(|()| save(&diff_path, test.size, &comparison.diff_image));
+    if let Err(e) = saved {
+        return Outcome::Error(e);
+    }
+
+    Outcome::Fail {
+        differing_pixels: comparison.differing_pixels,
+        max_difference: comparison.max_difference,
+    }
+}
+
+/// Renders every test headlessly and compares (or, when blessing, replaces)
+/// the references. Returns whether all of them passed.
+pub fn run_reftests(tests: &[RefTest], options: &RefTestOptions, cli: &cli::Options) -> bool {
+    let mut failures = 0;
+
+    for test in tests {
+        let label = test.file_name("");
+        match run_reftest(test, options, cli) {
+            Outcome::Pass => println!("PASS {}", label),
+            Outcome::Blessed => println!("BLESSED {}", label),
+            Outcome::MissingReference => {
+                failures += 1;
+                println!("FAIL {}: no reference image, run with --bless to create it", label);
+            }
+            Outcome::SizeMismatch => {
+                failures += 1;
+                println!("FAIL {}: reference image has the wrong size", label);
+            }
+            Outcome::Fail { differing_pixels, max_difference } => {
+                failures += 1;
+                println!("FAIL {}: {} pixels differ (max difference {}), see {}",
+                         label, differing_pixels, max_difference, options.output_dir.display());
+            }
+            Outcome::Error(e) => {
+                failures += 1;
+                println!("FAIL {}: {}", label, e);
+            }
+        }
+    }
+
+    println!("{} of {} reftests passed", tests.len() - failures, tests.len());
+    failures == 0
+}
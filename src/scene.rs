@@ -0,0 +1,341 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A declarative YAML description of a display list. See `scenes/` for
+//! examples of the format.
+
+use boilerplate::Example;
+use glutin;
+use serde_yaml;
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use webrender::api::*;
+
+/// `[x, y, width, height]`
+pub type SceneRect = [f32; 4];
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum SceneColor {
+    Named(String),
+    Rgb([f32; 3]),
+    Rgba([f32; 4]),
+}
+
+impl SceneColor {
+    fn to_color(&self) -> Result<ColorF, SceneError> {
+        Ok(match *self {
+            SceneColor::Rgb(c) => ColorF::new(c[0], c[1], c[2], 1.0),
+            SceneColor::Rgba(c) => ColorF::new(c[0], c[1], c[2], c[3]),
+            SceneColor::Named(ref name) => match name.as_str() {
+                "white" => ColorF::new(1.0, 1.0, 1.0, 1.0),
+                "black" => ColorF::new(0.0, 0.0, 0.0, 1.0),
+                "gray" => ColorF::new(0.5, 0.5, 0.5, 1.0),
+                "red" => ColorF::new(1.0, 0.0, 0.0, 1.0),
+                "green" => ColorF::new(0.0, 1.0, 0.0, 1.0),
+                "blue" => ColorF::new(0.0, 0.0, 1.0, 1.0),
+                "yellow" => ColorF::new(1.0, 1.0, 0.0, 1.0),
+                "cyan" => ColorF::new(0.0, 1.0, 1.0, 1.0),
+                "magenta" => ColorF::new(1.0, 0.0, 1.0, 1.0),
+                "transparent" => ColorF::new(0.0, 0.0, 0.0, 0.0),
+                _ => return Err(SceneError::UnknownColor(name.clone())),
+            },
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SceneItem {
+    Rect {
+        bounds: SceneRect,
+        color: SceneColor,
+        #[serde(default)]
+        clip: Option<SceneRect>,
+    },
+    StackingContext {
+        bounds: SceneRect,
+        #[serde(default)]
+        items: Vec<SceneItem>,
+    },
+    ScrollFrame {
+        /// The visible part of the frame.
+        clip: SceneRect,
+        /// The scrollable area, usually larger than `clip`.
+        content: SceneRect,
+        #[serde(default)]
+        items: Vec<SceneItem>,
+    },
+    Clip {
+        bounds: SceneRect,
+        #[serde(default)]
+        radius: f32,
+        #[serde(default)]
+        items: Vec<SceneItem>,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Scene {
+    #[serde(default)]
+    pub items: Vec<SceneItem>,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Parse(serde_yaml::Error),
+    UnknownColor(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SceneError::Io(ref e) => write!(f, "couldn't read scene: {}", e),
+            SceneError::Parse(ref e) => write!(f, "couldn't parse scene: {}", e),
+            SceneError::UnknownColor(ref name) => write!(f, "unknown color {:?}", name),
+        }
+    }
+}
+
+impl error::Error for SceneError {
+    fn description(&self) -> &str {
+        match *self {
+            SceneError::Io(..) => "couldn't read scene",
+            SceneError::Parse(..) => "couldn't parse scene",
+            SceneError::UnknownColor(..) => "unknown color",
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(e: io::Error) -> SceneError {
+        SceneError::Io(e)
+    }
+}
+
+impl From<serde_yaml::Error> for SceneError {
+    fn from(e: serde_yaml::Error) -> SceneError {
+        SceneError::Parse(e)
+    }
+}
+
+fn to_rect(r: &SceneRect) -> LayoutRect {
+    LayoutRect::new(LayoutPoint::new(r[0], r[1]), LayoutSize::new(r[2], r[3]))
+}
+
+impl Scene {
+    pub fn load(path: &Path) -> Result<Scene, SceneError> {
+        let mut source = String::new();
+        File::open(path)?.read_to_string(&mut source)?;
+        Scene::parse(&source)
+    }
+
+    pub fn parse(source: &str) -> Result<Scene, SceneError> {
+        let scene: Scene = serde_yaml::from_str(source)?;
+        // Catch bad colors at load time rather than halfway through a build.
+        scene.check_colors(&scene.items)?;
+        Ok(scene)
+    }
+
+    fn check_colors(&self, items: &[SceneItem]) -> Result<(), SceneError> {
+        for item in items {
+            match *item {
+                SceneItem::Rect { ref color, .. } => { color.to_color()?; }
+                SceneItem::StackingContext { ref items, .. } |
+                SceneItem::ScrollFrame { ref items, .. } |
+                SceneItem::Clip { ref items, .. } => self.check_colors(items)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Translates the scene into display items. Nothing is pushed around the
+    /// items, so the caller provides the root stacking context.
+    pub fn build(&self, builder: &mut DisplayListBuilder) {
+        build_items(&self.items, builder);
+    }
+}
+
+fn build_items(items: &[SceneItem], builder: &mut DisplayListBuilder) {
+    for item in items {
+        match *item {
+            SceneItem::Rect { ref bounds, ref color, ref clip } => {
+                let color = color.to_color().expect("colors are checked on load");
+                builder.push_rect(to_rect(bounds), clip.as_ref().map(|c| LocalClip::from(to_rect(c))), color);
+            }
+            SceneItem::StackingContext { ref bounds, ref items } => {
+                builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                              to_rect(bounds),
+                                              None,
+                                              TransformStyle::Flat,
+                                              None,
+                                              MixBlendMode::Normal,
+                                              Vec::new());
+                build_items(items, builder);
+                builder.pop_stacking_context();
+            }
+            SceneItem::ScrollFrame { ref clip, ref content, ref items } => {
+                let clip_id = builder.define_scroll_frame(None,
+                                                          to_rect(content),
+                                                          to_rect(clip),
+                                                          vec![],
+                                                          None,
+                                                          ScrollSensitivity::ScriptAndInputEvents);
+                builder.push_clip_id(clip_id);
+                build_items(items, builder);
+                builder.pop_clip_id();
+            }
+            SceneItem::Clip { ref bounds, radius, ref items } => {
+                let rect = to_rect(bounds);
+                let complex = vec![ComplexClipRegion::new(rect, BorderRadius::uniform(radius))];
+                let clip_id = builder.define_clip(None, rect, complex, None);
+                builder.push_clip_id(clip_id);
+                build_items(items, builder);
+                builder.pop_clip_id();
+            }
+        }
+    }
+}
+
+/// Renders the scene file given on the command line, reloading it whenever
+/// it changes.
+pub struct SceneExample {
+    path: PathBuf,
+    scene: Scene,
+}
+
+impl SceneExample {
+    pub fn new(path: PathBuf) -> Result<SceneExample, SceneError> {
+        let scene = Scene::load(&path)?;
+        Ok(SceneExample {
+            path,
+            scene,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Example for SceneExample {
+    fn render(&mut self,
+              _api: &RenderApi,
+              builder: &mut DisplayListBuilder,
+              _resources: &mut ResourceUpdates,
+              layout_size: LayoutSize,
+              _pipeline_id: PipelineId,
+              _document_id: DocumentId) {
+        let bounds = LayoutRect::new(LayoutPoint::zero(), layout_size);
+        builder.push_stacking_context(ScrollPolicy::Scrollable,
+                                      bounds,
+                                      None,
+                                      TransformStyle::Flat,
+                                      None,
+                                      MixBlendMode::Normal,
+                                      Vec::new());
+        self.scene.build(builder);
+        builder.pop_stacking_context();
+    }
+
+    fn on_event(&mut self,
+                event: glutin::WindowEvent,
+                _api: &RenderApi,
+                _document_id: DocumentId) -> bool {
+        match event {
+            glutin::WindowEvent::Resized(..) => true,
+            _ => false,
+        }
+    }
+
+    fn watched_files(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+
+    fn on_file_changed(&mut self, path: &Path) -> bool {
+        // Other examples' files and the shaders are watched too.
+        if path != self.path {
+            return false;
+        }
+        // Keep showing the old scene while the file doesn't parse, it's
+        // probably only half saved.
+        match Scene::load(&self.path) {
+            Ok(scene) => {
+                self.scene = scene;
+                true
+            }
+            Err(e) => {
+                error!("Couldn't reload {}: {}", self.path.display(), e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_items_and_colors_load() {
+        let scene = Scene::parse("
+items:
+  - type: stacking-context
+    bounds: [10, 10, 0, 0]
+    items:
+      - type: rect
+        bounds: [0, 0, 50, 50]
+        color: blue
+      - type: rect
+        bounds: [50, 0, 50, 50]
+        color: [0.5, 0.5, 0.5, 0.5]
+").unwrap();
+        match scene.items[0] {
+            SceneItem::StackingContext { ref items, .. } => assert_eq!(items.len(), 2),
+            ref item => panic!("expected a stacking context, got {:?}", item),
+        }
+    }
+
+    #[test]
+    fn unknown_color_names_are_rejected() {
+        let result = Scene::parse("
+items:
+  - type: clip
+    bounds: [0, 0, 100, 100]
+    items:
+      - type: rect
+        bounds: [0, 0, 50, 50]
+        color: mauve
+");
+        match result {
+            Err(SceneError::UnknownColor(ref name)) => assert_eq!(name, "mauve"),
+            other => panic!("expected an unknown color, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn colors_with_the_wrong_number_of_channels_are_rejected() {
+        match Scene::parse("items: [{type: rect, bounds: [0, 0, 10, 10], color: [1, 0]}]") {
+            Err(SceneError::Parse(..)) => {}
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_item_kinds_are_rejected() {
+        match Scene::parse("items: [{type: circle, bounds: [0, 0, 10, 10]}]") {
+            Err(SceneError::Parse(..)) => {}
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn an_empty_scene_has_no_items() {
+        assert!(Scene::parse("{}").unwrap().items.is_empty());
+    }
+}
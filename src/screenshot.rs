@@ -0,0 +1,44 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use gleam::gl;
+use image;
+use std::io;
+use std::path::Path;
+use webrender::api::DeviceUintSize;
+
+/// Reads back the currently bound read framebuffer as tightly packed RGBA8
+/// rows, top row first.
+pub fn read_pixels_rgba8(gl: &gl::Gl, size: DeviceUintSize) -> Vec<u8> {
+    gl.pixel_store_i(gl::PACK_ALIGNMENT, 1);
+    let pixels = gl.read_pixels(0, 0,
+                                size.width as gl::GLsizei,
+                                size.height as gl::GLsizei,
+                                gl::RGBA,
+                                gl::UNSIGNED_BYTE);
+    flip_rows(&pixels, size)
+}
+
+// GL hands rows back bottom-up, image files want them top-down.
+fn flip_rows(pixels: &[u8], size: DeviceUintSize) -> Vec<u8> {
+    let stride = size.width as usize * 4;
+    let mut flipped = Vec::with_capacity(pixels.len());
+    for row in pixels.chunks(stride).rev() {
+        flipped.extend_from_slice(row);
+    }
+    flipped
+}
+
+pub fn save_png(path: &Path, size: DeviceUintSize, pixels: &[u8]) -> io::Result<()> {
+    image::save_buffer(path, pixels, size.width, size.height, image::RGBA(8))
+}
+
+/// Reads back the current framebuffer and writes it to `path` as a PNG.
+pub fn save_screenshot(gl: &gl::Gl, size: DeviceUintSize, path: &Path) {
+    let pixels = read_pixels_rgba8(gl, size);
+    match save_png(path, size, &pixels) {
+        Ok(()) => info!("Saved screenshot to {}", path.display()),
+        Err(e) => error!("Failed to save screenshot to {}: {}", path.display(), e),
+    }
+}
@@ -1,101 +1,372 @@
-use std::ffi::CStr;
-use std::mem;
-use std::ptr;
-use glutin::{self, GlContext};
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A small layer over raw GL for examples that draw with their own shaders,
+//! like `examples/external.rs`.
+//!
+//! Creating and drawing with these changes GL bindings. When the context is
+//! shared with WebRender wrap that in `SavedBindings::save`/`restore`, since
+//! WebRender caches what it has bound.
+
 use gleam::gl;
+use std::error;
+use std::fmt;
+use std::mem;
 use std::rc::Rc;
+use std::slice;
 
-pub struct GlTest {
+#[derive(Debug)]
+pub enum GlError {
+    Compile {
+        stage: &'static str,
+        log: String,
+    },
+    Link {
+        log: String,
+    },
+    MissingAttribute(String),
+    /// A vertex layout with no size, or vertices whose type doesn't hold a
+    /// whole number of them.
+    BadVertexLayout {
+        stride: usize,
+        vertex_size: usize,
+    },
+    /// With the status `glCheckFramebufferStatus` returned.
+    IncompleteFramebuffer(gl::GLenum),
+}
+
+impl fmt::Display for GlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GlError::Compile { stage, ref log } => write!(f, "couldn't compile {} shader:\n{}", stage, log),
+            GlError::Link { ref log } => write!(f, "couldn't link program:\n{}", log),
+            GlError::MissingAttribute(ref name) => write!(f, "the program has no attribute {:?}", name),
+            GlError::BadVertexLayout { stride, vertex_size } => {
+                write!(f, "vertices of {} bytes don't match a layout of {} bytes", vertex_size, stride)
+            }
+            GlError::IncompleteFramebuffer(status) => write!(f, "the framebuffer is incomplete (status {:#x})", status),
+        }
+    }
+}
+
+impl error::Error for GlError {
+    fn description(&self) -> &str {
+        match *self {
+            GlError::Compile { .. } => "couldn't compile shader",
+            GlError::Link { .. } => "couldn't link program",
+            GlError::MissingAttribute(..) => "missing vertex attribute",
+            GlError::BadVertexLayout { .. } => "bad vertex layout",
+            GlError::IncompleteFramebuffer(..) => "incomplete framebuffer",
+        }
+    }
+}
+
+/// What goes before shader bodies so the same `in`/`out` GLSL works on both
+/// desktop GL 3.2 core and GLES 3.
+pub fn shader_prefix(gl: &gl::Gl) -> &'static str {
+    match gl.get_type() {
+        gl::GlType::Gl => "#version 150\n",
+        gl::GlType::Gles => "#version 300 es\nprecision mediump float;\n",
+    }
+}
+
+fn compile_shader(gl: &gl::Gl, kind: gl::GLenum, body: &str) -> Result<gl::GLuint, GlError> {
+    let shader = gl.create_shader(kind);
+    gl.shader_source(shader, &[shader_prefix(gl).as_bytes(), body.as_bytes()]);
+    gl.compile_shader(shader);
+    if gl.get_shader_iv(shader, gl::COMPILE_STATUS) == 0 {
+        let log = gl.get_shader_info_log(shader);
+        gl.delete_shader(shader);
+        let stage = if kind == gl::VERTEX_SHADER { "vertex" } else { "fragment" };
+        return Err(GlError::Compile { stage, log });
+    }
+    Ok(shader)
+}
+
+pub struct Program {
     gl: Rc<gl::Gl>,
+    id: gl::GLuint,
 }
 
-pub fn load(gl: Rc<gl::Gl>) -> GlTest {
-    let version = gl.get_string(gl::VERSION);
+impl Program {
+    /// Compiles and links the two shaders. The bodies get `shader_prefix`
+    /// prepended, so they must not have a `#version` line.
+    pub fn new(gl: Rc<gl::Gl>, vertex_body: &str, fragment_body: &str) -> Result<Program, GlError> {
+        let vs = compile_shader(&*gl, gl::VERTEX_SHADER, vertex_body)?;
+        let fs = match compile_shader(&*gl, gl::FRAGMENT_SHADER, fragment_body) {
+            Ok(fs) => fs,
+            Err(e) => {
+                gl.delete_shader(vs);
+                return Err(e);
+            }
+        };
 
-    println!("OpenGL version {}", version);
+        let id = gl.create_program();
+        gl.attach_shader(id, vs);
+        gl.attach_shader(id, fs);
+        gl.link_program(id);
 
-    let vs = gl.create_shader(gl::VERTEX_SHADER);
-    gl.shader_source(vs, &[VS_SRC]);
-    gl.compile_shader(vs);
-    println!("vs status: {}", gl.get_shader_iv(vs, gl::COMPILE_STATUS));
+        // The program keeps what it needs of the shaders.
+        gl.detach_shader(id, vs);
+        gl.detach_shader(id, fs);
+        gl.delete_shader(vs);
+        gl.delete_shader(fs);
 
-    let fs = gl.create_shader(gl::FRAGMENT_SHADER);
-    gl.shader_source(fs, &[FS_SRC]);
-    gl.compile_shader(fs);
-    println!("fs status: {}", gl.get_shader_iv(fs, gl::COMPILE_STATUS));
+        if gl.get_program_iv(id, gl::LINK_STATUS) == 0 {
+            let log = gl.get_program_info_log(id);
+            gl.delete_program(id);
+            return Err(GlError::Link { log });
+        }
 
-    let program = gl.create_program();
-    gl.attach_shader(program, vs);
-    gl.attach_shader(program, fs);
-    gl.link_program(program);
-    gl.use_program(program);
+        Ok(Program { gl, id })
+    }
 
-    // println!("vs status: {:?}", log);
-    // let log = gl.get_shader_iv(fs, gl::COMPILE_STATUS);
-    // println!("fs status: {:?}", log);
+    pub fn id(&self) -> gl::GLuint {
+        self.id
+    }
 
-    let vb = gl.gen_buffers(1)[0];
-    gl.bind_buffer(gl::ARRAY_BUFFER, vb);
-    gl.buffer_data_untyped(gl::ARRAY_BUFFER,
-                       (VERTEX_DATA.len() * mem::size_of::<f32>()) as gl::types::GLsizeiptr,
-                       VERTEX_DATA.as_ptr() as *const _, gl::STATIC_DRAW);
+    pub fn bind(&self) {
+        self.gl.use_program(self.id);
+    }
+
+    pub fn attrib_location(&self, name: &str) -> Result<gl::GLuint, GlError> {
+        match self.gl.get_attrib_location(self.id, name) {
+            -1 => Err(GlError::MissingAttribute(name.to_string())),
+            location => Ok(location as gl::GLuint),
+        }
+    }
 
-    let vao = gl.gen_vertex_arrays(1)[0];
-    gl.bind_vertex_array(vao);
+    /// `None` if there's no such uniform, or the compiler optimized it out.
+    pub fn uniform_location(&self, name: &str) -> Option<gl::GLint> {
+        match self.gl.get_uniform_location(self.id, name) {
+            -1 => None,
+            location => Some(location),
+        }
+    }
+}
 
-    let pos_attrib = gl.get_attrib_location(program, "position");
-    let color_attrib = gl.get_attrib_location(program, "color");
-    gl.vertex_attrib_pointer(pos_attrib as gl::types::GLuint, 2, gl::FLOAT, false,
-                                5 * mem::size_of::<f32>() as gl::types::GLsizei,
-                                0);
-    gl.vertex_attrib_pointer(color_attrib as gl::types::GLuint, 3, gl::FLOAT, false,
-                                5 * mem::size_of::<f32>() as gl::types::GLsizei,
-                                (2 * mem::size_of::<f32>()) as u32);
-    gl.enable_vertex_attrib_array(pos_attrib as gl::types::GLuint);
-    gl.enable_vertex_attrib_array(color_attrib as gl::types::GLuint);
+impl Drop for Program {
+    fn drop(&mut self) {
+        self.gl.delete_program(self.id);
+    }
+}
 
-    GlTest { gl: gl }
+#[derive(Clone, Copy, Debug)]
+pub enum AttributeKind {
+    Float,
+    /// Bytes read as 0.0 to 1.0.
+    UnsignedByteNormalized,
+}
+
+impl AttributeKind {
+    fn gl_type(&self) -> gl::GLenum {
+        match *self {
+            AttributeKind::Float => gl::FLOAT,
+            AttributeKind::UnsignedByteNormalized => gl::UNSIGNED_BYTE,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match *self {
+            AttributeKind::Float => mem::size_of::<f32>(),
+            AttributeKind::UnsignedByteNormalized => mem::size_of::<u8>(),
+        }
+    }
+}
+
+/// One attribute of an interleaved vertex. A layout is a slice of these in
+/// the order they appear in the vertex.
+#[derive(Clone, Copy, Debug)]
+pub struct VertexAttribute {
+    pub name: &'static str,
+    pub components: usize,
+    pub kind: AttributeKind,
+}
+
+impl VertexAttribute {
+    pub fn float(name: &'static str, components: usize) -> VertexAttribute {
+        VertexAttribute { name, components, kind: AttributeKind::Float }
+    }
+
+    pub fn unorm8(name: &'static str, components: usize) -> VertexAttribute {
+        VertexAttribute { name, components, kind: AttributeKind::UnsignedByteNormalized }
+    }
+
+    fn size(&self) -> usize {
+        self.components * self.kind.size()
+    }
+}
+
+/// A vertex buffer with the vertex array describing it.
+pub struct Mesh {
+    gl: Rc<gl::Gl>,
+    vbo: gl::GLuint,
+    vao: gl::GLuint,
+    stride: usize,
+    vertex_count: usize,
+    primitive: gl::GLenum,
+}
+
+impl Mesh {
+    /// `vertices` must be laid out as `layout` says. `T` is usually a
+    /// `#[repr(C)]` struct or an array of floats holding one vertex, and its
+    /// size has to be a multiple of the layout's.
+    pub fn new<T: Copy>(gl: Rc<gl::Gl>,
+                        program: &Program,
+                        layout: &[VertexAttribute],
+                        vertices: &[T],
+                        primitive: gl::GLenum) -> Result<Mesh, GlError> {
+        let stride: usize = layout.iter().map(VertexAttribute::size).sum();
+        let vertex_size = mem::size_of::<T>();
+        if stride == 0 || vertex_size % stride != 0 {
+            return Err(GlError::BadVertexLayout { stride, vertex_size });
+        }
+        let mut locations = Vec::with_capacity(layout.len());
+        for attribute in layout {
+            locations.push(program.attrib_location(attribute.name)?);
+        }
+
+        let vao = gl.gen_vertex_arrays(1)[0];
+        gl.bind_vertex_array(vao);
+        let vbo = gl.gen_buffers(1)[0];
+        gl.bind_buffer(gl::ARRAY_BUFFER, vbo);
+
+        let mut offset = 0;
+        for (attribute, &location) in layout.iter().zip(&locations) {
+            let normalized = match attribute.kind {
+                AttributeKind::Float => false,
+                AttributeKind::UnsignedByteNormalized => true,
+            };
+            gl.vertex_attrib_pointer(location,
+                                     attribute.components as gl::GLint,
+                                     attribute.kind.gl_type(),
+                                     normalized,
+                                     stride as gl::GLsizei,
+                                     offset as u32);
+            gl.enable_vertex_attrib_array(location);
+            offset += attribute.size();
+        }
+
+        let mut mesh = Mesh {
+            gl,
+            vbo,
+            vao,
+            stride,
+            vertex_count: 0,
+            primitive,
+        };
+        mesh.update(vertices);
+        Ok(mesh)
+    }
+
+    /// Replaces the vertices, which must have the type and layout the mesh
+    /// was made with.
+    pub fn update<T: Copy>(&mut self, vertices: &[T]) {
+        let size = vertices.len() * mem::size_of::<T>();
+        let bytes = unsafe { slice::from_raw_parts(vertices.as_ptr() as *const u8, size) };
+        self.gl.bind_buffer(gl::ARRAY_BUFFER, self.vbo);
+        self.gl.buffer_data_untyped(gl::ARRAY_BUFFER,
+                                    size as gl::types::GLsizeiptr,
+                                    bytes.as_ptr() as *const _,
+                                    gl::STATIC_DRAW);
+        self.vertex_count = size / self.stride;
+    }
+
+    pub fn draw(&self, program: &Program) {
+        program.bind();
+        self.gl.bind_vertex_array(self.vao);
+        self.gl.draw_arrays(self.primitive, 0, self.vertex_count as gl::GLsizei);
+    }
+}
+
+impl Drop for Mesh {
+    fn drop(&mut self) {
+        self.gl.delete_vertex_arrays(&[self.vao]);
+        self.gl.delete_buffers(&[self.vbo]);
+    }
+}
+
+/// GL bindings that WebRender keeps track of itself, so anything touching
+/// them behind its back has to put them back.
+pub struct SavedBindings {
+    active_texture: gl::GLuint,
+    texture: gl::GLuint,
+    draw_framebuffer: gl::GLuint,
+    read_framebuffer: gl::GLuint,
+    program: gl::GLuint,
+    vertex_array: gl::GLuint,
+    array_buffer: gl::GLuint,
+}
+
+impl SavedBindings {
+    pub fn save(gl: &gl::Gl) -> SavedBindings {
+        let get = |name| gl.get_integer_v(name) as gl::GLuint;
+        SavedBindings {
+            active_texture: get(gl::ACTIVE_TEXTURE),
+            texture: get(gl::TEXTURE_BINDING_2D),
+            draw_framebuffer: get(gl::DRAW_FRAMEBUFFER_BINDING),
+            read_framebuffer: get(gl::READ_FRAMEBUFFER_BINDING),
+            program: get(gl::CURRENT_PROGRAM),
+            vertex_array: get(gl::VERTEX_ARRAY_BINDING),
+            array_buffer: get(gl::ARRAY_BUFFER_BINDING),
+        }
+    }
+
+    pub fn restore(&self, gl: &gl::Gl) {
+        gl.active_texture(self.active_texture);
+        gl.bind_texture(gl::TEXTURE_2D, self.texture);
+        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, self.draw_framebuffer);
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, self.read_framebuffer);
+        gl.use_program(self.program);
+        gl.bind_vertex_array(self.vertex_array);
+        gl.bind_buffer(gl::ARRAY_BUFFER, self.array_buffer);
+    }
+}
+
+/// A triangle with a red, a green and a blue corner.
+pub struct GlTest {
+    gl: Rc<gl::Gl>,
+    program: Program,
+    mesh: Mesh,
+}
+
+pub fn load(gl: Rc<gl::Gl>) -> Result<GlTest, GlError> {
+    debug!("OpenGL version {}", gl.get_string(gl::VERSION));
+
+    let program = Program::new(gl.clone(), VS_SRC, FS_SRC)?;
+    let layout = [VertexAttribute::float("position", 2), VertexAttribute::float("color", 3)];
+    let mesh = Mesh::new(gl.clone(), &program, &layout, &VERTEX_DATA, gl::TRIANGLES)?;
+
+    Ok(GlTest { gl, program, mesh })
 }
 
 impl GlTest {
     pub fn draw_frame(&self, color: [f32; 4]) {
         self.gl.clear_color(color[0], color[1], color[2], color[3]);
         self.gl.clear(gl::COLOR_BUFFER_BIT);
-        self.gl.draw_arrays(gl::TRIANGLES, 0, 3);
-
-        // let log = self.gl.get_shader_iv(1, gl::COMPILE_STATUS);
-        // println!("vs status: {:?}", log);
-        // let log = self.gl.get_shader_iv(2, gl::COMPILE_STATUS);
-        // println!("fs status: {:?}", log);
-
-        // let log = self.gl.get_shader_info_log(1);
-        // println!("vs log: {:?}", log);
-        // let log = self.gl.get_shader_info_log(2);
-        // println!("fs log: {:?}", log);
+        self.mesh.draw(&self.program);
     }
 }
 
-static VERTEX_DATA: [f32; 15] = [
-    -0.5, -0.5, 1.0, 0.0, 0.0,
-    0.0, 0.5, 0.0, 1.0, 0.0,
-    0.5, -0.5, 0.0, 0.0, 1.0
+static VERTEX_DATA: [[f32; 5]; 3] = [
+    [-0.5, -0.5, 1.0, 0.0, 0.0],
+    [0.0, 0.5, 0.0, 1.0, 0.0],
+    [0.5, -0.5, 0.0, 0.0, 1.0],
 ];
 
-const VS_SRC: &'static [u8] = b"
-#version 100
-precision mediump float;
-attribute vec2 position;
-attribute vec3 color;
-varying vec3 v_color;
+const VS_SRC: &'static str = "
+in vec2 position;
+in vec3 color;
+out vec3 v_color;
 void main() {
     gl_Position = vec4(position, 0.0, 1.0);
     v_color = color;
 }";
 
-const FS_SRC: &'static [u8] = b"
-#version 100
-precision mediump float;
-varying vec3 v_color;
+const FS_SRC: &'static str = "
+in vec3 v_color;
+out vec4 out_color;
 void main() {
-    gl_FragColor = vec4(v_color, 1.0);
+    out_color = vec4(v_color, 1.0);
 }";
@@ -0,0 +1,230 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Fonts and simple text layout. `rusttype` reads the font to find glyphs
+//! and advances, WebRender rasterizes the same font file. Use it through
+//! `boilerplate::TextBuilder`.
+
+use app_units::Au;
+use rusttype;
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use webrender::api::*;
+
+/// Tried in order by `Font::system_default`.
+const SYSTEM_FONTS: &'static [&'static str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/Library/Fonts/Arial.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(io::Error),
+    Invalid(PathBuf),
+    NoSystemFont,
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FontError::Io(ref e) => write!(f, "couldn't read font: {}", e),
+            FontError::Invalid(ref path) => write!(f, "{} isn't a usable font", path.display()),
+            FontError::NoSystemFont => write!(f, "no system font found, tried {}", SYSTEM_FONTS.join(", ")),
+        }
+    }
+}
+
+impl error::Error for FontError {
+    fn description(&self) -> &str {
+        match *self {
+            FontError::Io(..) => "couldn't read font",
+            FontError::Invalid(..) => "not a usable font",
+            FontError::NoSystemFont => "no system font found",
+        }
+    }
+}
+
+impl From<io::Error> for FontError {
+    fn from(e: io::Error) -> FontError {
+        FontError::Io(e)
+    }
+}
+
+/// A font file. It's only sent to WebRender once an instance of it is
+/// requested, and sent again if the `RenderApi` changed since (the shader
+/// reload does that).
+pub struct Font {
+    bytes: Vec<u8>,
+    font: rusttype::Font<'static>,
+    key: Option<FontKey>,
+    instances: Vec<(Au, FontInstanceKey)>,
+}
+
+impl Font {
+    pub fn load(path: &Path) -> Result<Font, FontError> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        let font = rusttype::FontCollection::from_bytes(bytes.clone())
+            .into_font()
+            .ok_or_else(|| FontError::Invalid(path.to_owned()))?;
+        Ok(Font {
+            bytes,
+            font,
+            key: None,
+            instances: Vec::new(),
+        })
+    }
+
+    /// The first of a few well-known sans-serif fonts that exists.
+    pub fn system_default() -> Result<Font, FontError> {
+        SYSTEM_FONTS.iter()
+            .map(Path::new)
+            .find(|path| path.exists())
+            .ok_or(FontError::NoSystemFont)
+            .and_then(Font::load)
+    }
+
+    /// The font at `size` layout pixels, adding it to `resources` if needed.
+    pub fn instance(&mut self,
+                    api: &RenderApi,
+                    resources: &mut ResourceUpdates,
+                    size: f32) -> FontInstance {
+        let font_key = match self.key {
+            Some(key) if key.0 == api.get_namespace_id() => key,
+            _ => {
+                let key = api.generate_font_key();
+                resources.add_raw_font(key, self.bytes.clone(), 0);
+                self.key = Some(key);
+                self.instances.clear();
+                key
+            }
+        };
+
+        let glyph_size = Au::from_f32_px(size);
+        let key = match self.instances.iter().find(|&&(s, _)| s == glyph_size) {
+            Some(&(_, key)) => key,
+            None => {
+                let key = api.generate_font_instance_key();
+                resources.add_font_instance(key, font_key, glyph_size, None, None);
+                self.instances.push((glyph_size, key));
+                key
+            }
+        };
+
+        FontInstance {
+            key,
+            size,
+            font: self.font.clone(),
+        }
+    }
+}
+
+/// Glyphs positioned by `FontInstance::layout`, ready for `push_text`.
+pub struct GlyphRun {
+    pub glyphs: Vec<GlyphInstance>,
+    /// From the top of the first line to the bottom of the last.
+    pub bounds: LayoutRect,
+}
+
+/// A font at a particular size.
+#[derive(Clone)]
+pub struct FontInstance {
+    pub key: FontInstanceKey,
+    pub size: f32,
+    font: rusttype::Font<'static>,
+}
+
+impl FontInstance {
+    fn scale(&self) -> rusttype::Scale {
+        rusttype::Scale::uniform(self.size)
+    }
+
+    pub fn ascent(&self) -> f32 {
+        self.font.v_metrics(self.scale()).ascent
+    }
+
+    /// The distance from one baseline to the next.
+    pub fn line_height(&self) -> f32 {
+        let metrics = self.font.v_metrics(self.scale());
+        metrics.ascent - metrics.descent + metrics.line_gap
+    }
+
+    /// Lays `text` out with its top left corner at `origin`. Lines break at
+    /// newlines and, given a `max_width`, at the last space that keeps them
+    /// narrower than it. There is no shaping beyond kerning, which is plenty
+    /// for labels and debug output.
+    pub fn layout(&self, text: &str, origin: LayoutPoint, max_width: Option<f32>) -> GlyphRun {
+        let scale = self.scale();
+        let line_height = self.line_height();
+        let descent = self.font.v_metrics(scale).descent;
+
+        let mut glyphs = Vec::new();
+        let mut pen_x = origin.x;
+        let mut baseline = origin.y + self.ascent();
+        let mut width: f32 = 0.0;
+        let mut previous = None;
+        // The glyph index after the last space on this line, and where the
+        // pen was at that point.
+        let mut break_point: Option<(usize, f32)> = None;
+
+        for c in text.chars() {
+            if c == '\n' {
+                pen_x = origin.x;
+                baseline += line_height;
+                previous = None;
+                break_point = None;
+                continue;
+            }
+
+            let glyph = self.font.glyph(c)
+                .or_else(|| self.font.glyph(rusttype::GlyphId(0)))
+                .expect("fonts have a .notdef glyph");
+            let id = glyph.id();
+            if let Some(previous) = previous {
+                pen_x += self.font.pair_kerning(scale, previous, id);
+            }
+            let advance = glyph.scaled(scale).h_metrics().advance_width;
+
+            let overflows = max_width.map_or(false, |max| pen_x + advance - origin.x > max);
+            if c != ' ' && overflows {
+                if let Some((index, x)) = break_point.take() {
+                    // Move the word being written down to a new line.
+                    let shift = x - origin.x;
+                    baseline += line_height;
+                    for glyph in &mut glyphs[index..] {
+                        glyph.point = LayoutPoint::new(glyph.point.x - shift, baseline);
+                    }
+                    pen_x -= shift;
+                }
+            }
+
+            glyphs.push(GlyphInstance {
+                index: id.0,
+                point: LayoutPoint::new(pen_x, baseline),
+            });
+            pen_x += advance;
+            previous = Some(id);
+
+            if c == ' ' {
+                break_point = Some((glyphs.len(), pen_x));
+            } else {
+                width = width.max(pen_x - origin.x);
+            }
+        }
+
+        let height = baseline - descent - origin.y;
+        GlyphRun {
+            glyphs,
+            bounds: LayoutRect::new(origin, LayoutSize::new(width, height)),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use glutin;
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL_MS: u64 = 250;
+
+/// Watches files and directories (one level deep) by polling their
+/// modification times on a background thread. The event loop is woken up
+/// whenever something changes, and the changes can then be fetched with
+/// `take_changes`.
+pub struct FileWatcher {
+    // Nothing panics while holding the lock, so it's never poisoned.
+    changes: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+fn modification_times(paths: &[PathBuf]) -> HashMap<PathBuf, SystemTime> {
+    let mut times = HashMap::new();
+    for path in paths {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if metadata.is_dir() {
+            let entries = match fs::read_dir(path) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                    times.insert(entry.path(), modified);
+                }
+            }
+        } else if let Ok(modified) = metadata.modified() {
+            times.insert(path.clone(), modified);
+        }
+    }
+    times
+}
+
+impl FileWatcher {
+    pub fn new(paths: Vec<PathBuf>, loop_proxy: glutin::EventsLoopProxy) -> FileWatcher {
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let thread_changes = changes.clone();
+
+        thread::spawn(move || {
+            let mut times = modification_times(&paths);
+            loop {
+                thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+                let new_times = modification_times(&paths);
+                let changed: Vec<PathBuf> = new_times.iter()
+                    .filter(|&(path, time)| times.get(path) != Some(time))
+                    .map(|(path, _)| path.clone())
+                    .collect();
+                times = new_times;
+
+                if changed.is_empty() {
+                    continue;
+                }
+                thread_changes.lock().unwrap().extend(changed);
+                // The event loop is gone, so nobody is interested any more.
+                if loop_proxy.wakeup().is_err() {
+                    return;
+                }
+            }
+        });
+
+        FileWatcher {
+            changes,
+        }
+    }
+
+    /// Returns the files that changed since the last call.
+    pub fn take_changes(&self) -> Vec<PathBuf> {
+        mem::replace(&mut *self.changes.lock().unwrap(), Vec::new())
+    }
+}
@@ -0,0 +1,609 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A retained tree of boxes, text, images and scroll containers, laid out in
+//! rows, columns or grids and turned into display items by
+//! `WidgetTree::build`.
+//!
+//! Examples keep the tree around and change it from their event handlers.
+//! Setters compare against what's there, so `needs_rebuild` only turns true
+//! for changes that show. There's no diffing of display items: WebRender
+//! takes whole display lists, so any change rebuilds the example's. What a
+//! change that only affects colors saves is the layout pass.
+
+use boilerplate::{ImageBuilder, TextBuilder};
+use hit_test::{HitTag, HitTester};
+use images::LoadedImage;
+use std::cmp;
+use text::Font;
+use webrender::api::*;
+
+/// A widget in a `WidgetTree`. Using the id of a removed widget is a bug
+/// in the caller and panics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// How a node places its children.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    /// Left to right, each as tall as the node.
+    Row,
+    /// Top to bottom, each as wide as the node.
+    Column,
+    /// In rows of `columns` equally sized cells that fill the node.
+    Grid { columns: usize },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+    pub layout: Layout,
+    /// Fixed sizes. Without them a node is as large as its content.
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    /// The node's share of the space its row or column has left over.
+    pub grow: f32,
+    pub padding: f32,
+    /// Between children.
+    pub gap: f32,
+    pub background: Option<ColorF>,
+    /// Clips the children to the node, and makes them scrollable when they
+    /// don't fit.
+    pub scroll: bool,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            layout: Layout::Column,
+            width: None,
+            height: None,
+            grow: 0.0,
+            padding: 0.0,
+            gap: 0.0,
+            background: None,
+            scroll: false,
+        }
+    }
+}
+
+impl Style {
+    // Whether `other` can be drawn without laying the tree out again.
+    fn same_layout(&self, other: &Style) -> bool {
+        Style { background: None, .. *self } == Style { background: None, .. *other }
+    }
+}
+
+/// What a node draws on top of its background, inside its padding.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Content {
+    None,
+    /// Wrapped at spaces to the node's width.
+    Text {
+        text: String,
+        size: f32,
+        color: ColorF,
+    },
+    /// Stretched to the node's size.
+    Image(LoadedImage),
+}
+
+impl Content {
+    pub fn text(text: &str, size: f32, color: ColorF) -> Content {
+        Content::Text {
+            text: text.to_string(),
+            size,
+            color,
+        }
+    }
+
+    fn same_layout(&self, other: &Content) -> bool {
+        match (self, other) {
+            (&Content::Text { text: ref a, size: size_a, .. },
+             &Content::Text { text: ref b, size: size_b, .. }) => a == b && size_a == size_b,
+            _ => self == other,
+        }
+    }
+}
+
+// What has to happen before the tree can be drawn again, in increasing
+// amounts of work.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Change {
+    None,
+    Paint,
+    Layout,
+}
+
+struct Node {
+    style: Style,
+    content: Content,
+    tag: Option<HitTag>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    // From the last layout: the size the node would like, where it ended up,
+    // and how far its children reach from the top left of its padding.
+    natural_size: LayoutSize,
+    rect: LayoutRect,
+    content_size: LayoutSize,
+}
+
+// The size `content` would like, inside the node's padding.
+fn measure_content(content: &Content,
+                   font: Option<&mut Font>,
+                   api: &RenderApi,
+                   resources: &mut ResourceUpdates) -> LayoutSize {
+    match *content {
+        Content::None => LayoutSize::zero(),
+        Content::Text { ref text, size, .. } => match font {
+            Some(font) => font.instance(api, resources, size).layout(text, LayoutPoint::zero(), None).bounds.size,
+            None => LayoutSize::zero(),
+        },
+        Content::Image(ref image) => image.size,
+    }
+}
+
+/// A tree of nodes under a root that covers the whole example. Ids of
+/// removed nodes get reused.
+pub struct WidgetTree {
+    nodes: Vec<Option<Node>>,
+    free: Vec<usize>,
+    root: NodeId,
+    font: Option<Font>,
+    hit_tester: HitTester,
+    change: Change,
+    layout_size: LayoutSize,
+}
+
+impl WidgetTree {
+    /// Measures and draws text with the system font, if there is one.
+    pub fn new(root_style: Style) -> WidgetTree {
+        let font = Font::system_default()
+            .map_err(|e| warn!("Widget text won't be drawn: {}", e))
+            .ok();
+        WidgetTree::with_font(root_style, font)
+    }
+
+    /// Without a font, text takes no space and isn't drawn.
+    pub fn with_font(root_style: Style, font: Option<Font>) -> WidgetTree {
+        let mut tree = WidgetTree {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NodeId(0),
+            font,
+            hit_tester: HitTester::new(),
+            change: Change::Layout,
+            layout_size: LayoutSize::zero(),
+        };
+        tree.root = tree.insert(None, root_style, Content::None);
+        tree
+    }
+
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        self.nodes[id.0].as_ref().expect("no such widget")
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        self.nodes[id.0].as_mut().expect("no such widget")
+    }
+
+    fn mark(&mut self, change: Change) {
+        self.change = cmp::max(self.change, change);
+    }
+
+    fn insert(&mut self, parent: Option<NodeId>, style: Style, content: Content) -> NodeId {
+        let node = Node {
+            style,
+            content,
+            tag: None,
+            parent,
+            children: Vec::new(),
+            natural_size: LayoutSize::zero(),
+            rect: LayoutRect::zero(),
+            content_size: LayoutSize::zero(),
+        };
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                NodeId(index)
+            }
+            None => {
+                self.nodes.push(Some(node));
+                NodeId(self.nodes.len() - 1)
+            }
+        }
+    }
+
+    /// Adds a node after the existing children of `parent`.
+    pub fn add(&mut self, parent: NodeId, style: Style, content: Content) -> NodeId {
+        let id = self.insert(Some(parent), style, content);
+        self.node_mut(parent).children.push(id);
+        self.mark(Change::Layout);
+        id
+    }
+
+    /// Removes `id` and everything under it. The root can't be removed, for
+    /// it this returns false and leaves the tree alone.
+    pub fn remove(&mut self, id: NodeId) -> bool {
+        if id == self.root {
+            return false;
+        }
+        if let Some(parent) = self.node(id).parent {
+            self.node_mut(parent).children.retain(|&child| child != id);
+        }
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            let node = self.nodes[id.0].take().expect("no such widget");
+            pending.extend(node.children);
+            self.free.push(id.0);
+        }
+        self.mark(Change::Layout);
+        true
+    }
+
+    pub fn style(&self, id: NodeId) -> &Style {
+        &self.node(id).style
+    }
+
+    pub fn set_style(&mut self, id: NodeId, style: Style) {
+        let change = {
+            let old = &self.node(id).style;
+            if *old == style {
+                return;
+            }
+            if old.same_layout(&style) { Change::Paint } else { Change::Layout }
+        };
+        self.node_mut(id).style = style;
+        self.mark(change);
+    }
+
+    pub fn set_content(&mut self, id: NodeId, content: Content) {
+        let change = {
+            let old = &self.node(id).content;
+            if *old == content {
+                return;
+            }
+            if old.same_layout(&content) { Change::Paint } else { Change::Layout }
+        };
+        self.node_mut(id).content = content;
+        self.mark(change);
+    }
+
+    /// Tags the node's rect in `hit_tester`, clipped to its scroll
+    /// containers.
+    pub fn set_tag(&mut self, id: NodeId, tag: Option<HitTag>) {
+        if self.node(id).tag != tag {
+            self.node_mut(id).tag = tag;
+            self.mark(Change::Paint);
+        }
+    }
+
+    /// Where the node was placed by the last `build`.
+    pub fn rect(&self, id: NodeId) -> LayoutRect {
+        self.node(id).rect
+    }
+
+    /// Whether anything changed since the last `build`.
+    pub fn needs_rebuild(&self) -> bool {
+        self.change != Change::None
+    }
+
+    pub fn hit_tester(&self) -> &HitTester {
+        &self.hit_tester
+    }
+
+    /// For tagging items built next to the tree.
+    pub fn hit_tester_mut(&mut self) -> &mut HitTester {
+        &mut self.hit_tester
+    }
+
+    /// Lays the tree out if needed and pushes its display items, without a
+    /// stacking context of its own.
+    pub fn build(&mut self,
+                 api: &RenderApi,
+                 builder: &mut DisplayListBuilder,
+                 resources: &mut ResourceUpdates,
+                 layout_size: LayoutSize) {
+        let root = self.root;
+        let mut font = self.font.take();
+        self.layout(layout_size, |content| measure_content(content, font.as_mut(), api, resources));
+        self.font = font;
+
+        self.hit_tester.clear();
+        self.build_node(root, api, builder, resources, LayoutRect::new(LayoutPoint::zero(), layout_size));
+        self.change = Change::None;
+    }
+
+    // Lays the tree out for `layout_size` unless nothing that affects the
+    // layout changed since the last time. Returns whether it did.
+    fn layout<F>(&mut self, layout_size: LayoutSize, mut measure_content: F) -> bool
+        where F: FnMut(&Content) -> LayoutSize
+    {
+        if self.change != Change::Layout && layout_size == self.layout_size {
+            return false;
+        }
+        let root = self.root;
+        self.measure(root, &mut measure_content);
+        self.arrange(root, LayoutRect::new(LayoutPoint::zero(), layout_size));
+        self.layout_size = layout_size;
+        // It still has to be drawn.
+        if self.change == Change::Layout {
+            self.change = Change::Paint;
+        }
+        true
+    }
+
+    // Works out the natural size of `id` and its children, bottom up.
+    fn measure<F>(&mut self, id: NodeId, measure_content: &mut F) -> LayoutSize
+        where F: FnMut(&Content) -> LayoutSize
+    {
+        let children = self.node(id).children.clone();
+        let child_sizes: Vec<_> = children.iter().map(|&child| self.measure(child, measure_content)).collect();
+        let content = measure_content(&self.node(id).content);
+
+        let size = {
+            let style = &self.node(id).style;
+            let gaps = style.gap * child_sizes.len().saturating_sub(1) as f32;
+            let widest = child_sizes.iter().fold(0.0f32, |max, size| max.max(size.width));
+            let tallest = child_sizes.iter().fold(0.0f32, |max, size| max.max(size.height));
+            let children = match style.layout {
+                Layout::Row => {
+                    LayoutSize::new(child_sizes.iter().map(|size| size.width).sum::<f32>() + gaps, tallest)
+                }
+                Layout::Column => {
+                    LayoutSize::new(widest, child_sizes.iter().map(|size| size.height).sum::<f32>() + gaps)
+                }
+                Layout::Grid { columns } => {
+                    let columns = columns.max(1);
+                    let rows = (child_sizes.len() + columns - 1) / columns;
+                    LayoutSize::new(columns as f32 * widest + style.gap * (columns - 1) as f32,
+                                    rows as f32 * tallest + style.gap * rows.saturating_sub(1) as f32)
+                }
+            };
+
+            let padding = 2.0 * style.padding;
+            LayoutSize::new(style.width.unwrap_or(content.width.max(children.width) + padding),
+                            style.height.unwrap_or(content.height.max(children.height) + padding))
+        };
+        self.node_mut(id).natural_size = size;
+        size
+    }
+
+    // Places `id` at `rect` and its children inside it, top down.
+    fn arrange(&mut self, id: NodeId, rect: LayoutRect) {
+        let (style, children) = {
+            let node = self.node_mut(id);
+            node.rect = rect;
+            (node.style, node.children.clone())
+        };
+        let inner = rect.inflate(-style.padding, -style.padding);
+        let sizes: Vec<_> = children.iter().map(|&child| self.node(child).natural_size).collect();
+        let grows: Vec<_> = children.iter().map(|&child| self.node(child).style.grow).collect();
+        let total_grow: f32 = grows.iter().sum();
+        let gaps = style.gap * children.len().saturating_sub(1) as f32;
+
+        let mut placed = Vec::with_capacity(children.len());
+        match style.layout {
+            Layout::Row => {
+                let used: f32 = sizes.iter().map(|size| size.width).sum::<f32>() + gaps;
+                let free = (inner.size.width - used).max(0.0);
+                let mut x = inner.origin.x;
+                for (size, grow) in sizes.iter().zip(&grows) {
+                    let extra = if total_grow > 0.0 { free * grow / total_grow } else { 0.0 };
+                    let width = size.width + extra;
+                    placed.push(LayoutRect::new(LayoutPoint::new(x, inner.origin.y),
+                                                LayoutSize::new(width, inner.size.height.max(size.height))));
+                    x += width + style.gap;
+                }
+            }
+            Layout::Column => {
+                let used: f32 = sizes.iter().map(|size| size.height).sum::<f32>() + gaps;
+                let free = (inner.size.height - used).max(0.0);
+                let mut y = inner.origin.y;
+                for (size, grow) in sizes.iter().zip(&grows) {
+                    let extra = if total_grow > 0.0 { free * grow / total_grow } else { 0.0 };
+                    let height = size.height + extra;
+                    placed.push(LayoutRect::new(LayoutPoint::new(inner.origin.x, y),
+                                                LayoutSize::new(inner.size.width.max(size.width), height)));
+                    y += height + style.gap;
+                }
+            }
+            Layout::Grid { columns } => {
+                let columns = columns.max(1);
+                let rows = (children.len() + columns - 1) / columns;
+                let tallest = sizes.iter().fold(0.0f32, |max, size| max.max(size.height));
+                let cell_width = ((inner.size.width - style.gap * (columns - 1) as f32) / columns as f32).max(0.0);
+                let cell_height = if rows == 0 {
+                    0.0
+                } else {
+                    ((inner.size.height - style.gap * (rows - 1) as f32) / rows as f32).max(tallest)
+                };
+                for index in 0..children.len() {
+                    let (row, column) = (index / columns, index % columns);
+                    let origin = LayoutPoint::new(inner.origin.x + column as f32 * (cell_width + style.gap),
+                                                  inner.origin.y + row as f32 * (cell_height + style.gap));
+                    placed.push(LayoutRect::new(origin, LayoutSize::new(cell_width, cell_height)));
+                }
+            }
+        }
+
+        let mut reach = inner.size;
+        for (&child, child_rect) in children.iter().zip(placed) {
+            self.arrange(child, child_rect);
+            let bottom_right = child_rect.bottom_right() - inner.origin;
+            reach.width = reach.width.max(bottom_right.x);
+            reach.height = reach.height.max(bottom_right.y);
+        }
+        self.node_mut(id).content_size = reach;
+    }
+
+    fn build_node(&mut self,
+                  id: NodeId,
+                  api: &RenderApi,
+                  builder: &mut DisplayListBuilder,
+                  resources: &mut ResourceUpdates,
+                  clip: LayoutRect) {
+        let (style, rect, content_size, tag, children) = {
+            let node = self.node(id);
+            (node.style, node.rect, node.content_size, node.tag, node.children.clone())
+        };
+        let inner = rect.inflate(-style.padding, -style.padding);
+
+        if let Some(color) = style.background {
+            builder.push_rect(rect, None, color);
+        }
+        match self.nodes[id.0].as_ref().expect("no such widget").content {
+            Content::None => (),
+            Content::Text { ref text, size, color } => {
+                if let Some(ref mut font) = self.font {
+                    let font = font.instance(api, resources, size);
+                    let run = font.layout(text, inner.origin, Some(inner.size.width));
+                    builder.push_glyph_run(&font, &run, color);
+                }
+            }
+            Content::Image(ref image) => builder.push_image_stretched(image, inner),
+        }
+        if let Some(tag) = tag {
+            self.hit_tester.tag_clipped(rect, clip, tag);
+        }
+
+        if style.scroll {
+            let clip_id = builder.define_scroll_frame(None,
+                                                      LayoutRect::new(inner.origin, content_size),
+                                                      inner,
+                                                      vec![],
+                                                      None,
+                                                      ScrollSensitivity::ScriptAndInputEvents);
+            builder.push_clip_id(clip_id);
+            let clip = clip.intersection(&inner).unwrap_or(LayoutRect::zero());
+            for child in children {
+                self.build_node(child, api, builder, resources, clip);
+            }
+            builder.pop_clip_id();
+        } else {
+            for child in children {
+                self.build_node(child, api, builder, resources, clip);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // Tests measure text with closures passed to `layout`, so they don't
+    // need a font from the system.
+    fn new_tree(root_style: Style) -> WidgetTree {
+        WidgetTree::with_font(root_style, None)
+    }
+
+    fn sized(width: f32, height: f32) -> Style {
+        Style { width: Some(width), height: Some(height), .. Style::default() }
+    }
+
+    fn no_content(_: &Content) -> LayoutSize {
+        LayoutSize::zero()
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> LayoutRect {
+        LayoutRect::new(LayoutPoint::new(x, y), LayoutSize::new(width, height))
+    }
+
+    #[test]
+    fn row_gives_free_space_to_growing_children() {
+        let mut tree = new_tree(Style { layout: Layout::Row, padding: 10.0, gap: 5.0, .. Style::default() });
+        let root = tree.root();
+        let fixed = tree.add(root, Style { width: Some(50.0), .. Style::default() }, Content::None);
+        let one = tree.add(root, Style { width: Some(20.0), grow: 1.0, .. Style::default() }, Content::None);
+        let three = tree.add(root, Style { width: Some(20.0), grow: 3.0, .. Style::default() }, Content::None);
+        tree.layout(LayoutSize::new(300.0, 100.0), no_content);
+
+        // 280 wide inside the padding, 100 of it used, so 45 and 135 extra.
+        assert_eq!(tree.rect(root), rect(0.0, 0.0, 300.0, 100.0));
+        assert_eq!(tree.rect(fixed), rect(10.0, 10.0, 50.0, 80.0));
+        assert_eq!(tree.rect(one), rect(65.0, 10.0, 65.0, 80.0));
+        assert_eq!(tree.rect(three), rect(135.0, 10.0, 155.0, 80.0));
+    }
+
+    #[test]
+    fn column_stacks_children_at_their_natural_height() {
+        let mut tree = new_tree(Style { padding: 4.0, gap: 2.0, .. Style::default() });
+        let root = tree.root();
+        let a = tree.add(root, sized(30.0, 10.0), Content::None);
+        let b = tree.add(root, sized(30.0, 20.0), Content::None);
+        tree.layout(LayoutSize::new(100.0, 100.0), no_content);
+
+        assert_eq!(tree.rect(a), rect(4.0, 4.0, 92.0, 10.0));
+        assert_eq!(tree.rect(b), rect(4.0, 16.0, 92.0, 20.0));
+    }
+
+    #[test]
+    fn grid_fills_the_node_with_equal_cells() {
+        let mut tree = new_tree(Style {
+            layout: Layout::Grid { columns: 3 },
+            padding: 10.0,
+            gap: 10.0,
+            .. Style::default()
+        });
+        let root = tree.root();
+        let cells: Vec<_> = (0..5).map(|_| tree.add(root, Style::default(), Content::None)).collect();
+        tree.layout(LayoutSize::new(320.0, 220.0), no_content);
+
+        // 300x200 inside the padding, two gaps across and one down.
+        assert_eq!(tree.rect(cells[0]), rect(10.0, 10.0, 280.0 / 3.0, 95.0));
+        assert_eq!(tree.rect(cells[4]), rect(10.0 + (280.0 / 3.0 + 10.0), 115.0, 280.0 / 3.0, 95.0));
+    }
+
+    #[test]
+    fn natural_size_includes_content_padding_and_gaps() {
+        let mut tree = new_tree(Style { layout: Layout::Row, .. Style::default() });
+        let root = tree.root();
+        let column = tree.add(root, Style { padding: 4.0, gap: 2.0, .. Style::default() }, Content::None);
+        tree.add(column, sized(10.0, 10.0), Content::None);
+        tree.add(column, sized(10.0, 10.0), Content::None);
+        let label = tree.add(root, Style { padding: 4.0, .. Style::default() },
+                             Content::text("label", 12.0, ColorF::new(0.0, 0.0, 0.0, 1.0)));
+        tree.layout(LayoutSize::new(200.0, 100.0), |content| match *content {
+            Content::Text { .. } => LayoutSize::new(40.0, 12.0),
+            _ => LayoutSize::zero(),
+        });
+
+        assert_eq!(tree.rect(column).size.width, 18.0);
+        assert_eq!(tree.rect(label), rect(18.0, 0.0, 48.0, 100.0));
+    }
+
+    #[test]
+    fn layout_is_skipped_when_nothing_moved() {
+        let measured = Cell::new(0);
+        let count = |_: &Content| {
+            measured.set(measured.get() + 1);
+            LayoutSize::zero()
+        };
+        let size = LayoutSize::new(100.0, 100.0);
+        let mut tree = new_tree(Style::default());
+        let root = tree.root();
+        let child = tree.add(root, sized(10.0, 10.0), Content::None);
+
+        assert!(tree.layout(size, &count));
+        assert_eq!(measured.get(), 2);
+        assert!(!tree.layout(size, &count));
+
+        // A new color only has to be drawn.
+        tree.set_style(child, Style { background: Some(ColorF::new(1.0, 0.0, 0.0, 1.0)), .. sized(10.0, 10.0) });
+        assert!(tree.needs_rebuild());
+        assert!(!tree.layout(size, &count));
+        assert_eq!(measured.get(), 2);
+
+        tree.set_style(child, sized(20.0, 10.0));
+        assert!(tree.layout(size, &count));
+        assert_eq!(tree.rect(child).size.height, 10.0);
+        assert!(tree.layout(LayoutSize::new(50.0, 50.0), &count));
+        assert_eq!(measured.get(), 6);
+    }
+}